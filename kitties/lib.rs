@@ -29,9 +29,29 @@
 //!
 //! ### Kitty Creation
 //!
-//! Kitty creation start by calling the `mint(&mut self, id: u32)` function.
+//! Kitty creation start by calling the `mint(&mut self, id: KittyId)` function.
 //! The kitty owner becomes the function caller. The Kitty ID needs to be specified
 //! as the argument on this function call.
+//! Alternatively `mint_auto(&mut self)` picks the next unused Kitty ID and returns it.
+//! Several kitties can be created with a single payment by calling
+//! `mint_batch(&mut self, ids: Vec<KittyId>)`.
+//!
+//! If the contract was created with a maximum supply, minting fails once that many
+//! kitties exist. Burned kitties do not count against the cap, so burning a kitty frees
+//! up a slot for a new one.
+//!
+//! Every kitty gets pseudo random genes when it is minted.
+//!
+//! ### Kitty Breeding
+//!
+//! Calling `breed(&mut self, parent1: KittyId, parent2: KittyId) -> Result<KittyId>` with
+//! two kitties the caller owns or is approved for creates a child kitty for the caller with
+//! the next unused Kitty ID, and returns that ID.
+//! The child's genes are mixed deterministically from its parents' genes.
+//! The child is one generation after its youngest parent, minted kitties being generation 0.
+//! Both parents then have to cool down for some blocks before they can breed again, and
+//! higher generations cool down longer.
+//! Breeding costs a fee instead of the mint price, which grows with the parents' generation.
 //!
 //! ### Kitty Transfer
 //!
@@ -47,18 +67,50 @@
 //!
 //! ### Kitty Removal
 //!
-//! Kitty token can be destroyed by burning them. Only the kitty token owner is allowed to burn a
-//! kitty token.
+//! Kitty token can be destroyed by burning them. The kitty token owner, the approved address
+//! of a kitty token or an authorized operator of the owner is allowed to burn a kitty token.
+//!
+//! ### Pausing
+//!
+//! In an emergency the contract owner can pause the contract. While paused, minting,
+//! transfers and approvals fail with `ContractPaused`, reading state keeps working.
 
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 pub use kitties::{Kitties, KittiesRef};
 
 #[ink::contract]
 mod kitties {
-    use ink::storage::Mapping;
-    use trait_erc721::{Error, Result, KittyId, TERC721};
+    use ink::{
+        codegen::TraitCallBuilder,
+        env::hash::{Blake2x256, HashOutput},
+        prelude::vec::Vec,
+        storage::Mapping,
+    };
+    use trait_erc721::{
+        Error, KittyId, Result, TKittiesMarket, TKittyTransferHook, TERC2981, TERC721,
+        TERC721Receiver, ON_ERC721_RECEIVED,
+    };
     use trait_erc20::TERC20;
 
+    /// Breeding cooldowns in blocks, indexed by kitty generation.
+    const BREEDING_COOLDOWNS: [BlockNumber; 8] = [10, 20, 50, 100, 300, 600, 1200, 2400];
+
+    /// Maximum number of holders scanned by `top_holders`, bounding its gas cost.
+    const MAX_HOLDER_SCAN: usize = 1000;
+
+    /// Maximum number of kitties returned by `tokens_of_owner`.
+    const MAX_PAGE_SIZE: u32 = 100;
+
+    /// ERC-165 interface ids reported by `supports_interface`.
+    const SUPPORTED_INTERFACES: [[u8; 4]; 3] = [
+        // ERC-165
+        [0x01, 0xff, 0xc9, 0xa7],
+        // ERC-721
+        [0x80, 0xac, 0x58, 0xcd],
+        // ERC-2981 royalties
+        [0x2a, 0x55, 0x20, 0x5a],
+    ];
+
     #[ink(storage)]
     pub struct Kitties {
         /// Mapping from kitty to owner.
@@ -67,12 +119,79 @@ mod kitties {
         token_approvals: Mapping<KittyId, AccountId>,
         /// Mapping from owner to number of owned kitty.
         owned_kitties_count: Mapping<AccountId, u32>,
-        /// Mapping from owner to operator approvals.
-        operator_approvals: Mapping<(AccountId, AccountId), ()>,
+        /// Mapping from owner and index to the kitties it owns, indexed from 0 to its
+        /// balance.
+        owned_tokens: Mapping<(AccountId, u32), KittyId>,
+        /// Mapping from kitty to its index in the kitties of its owner.
+        owned_tokens_index: Mapping<KittyId, u32>,
+        /// Mapping from owner and operator to the last block the operator is approved for.
+        operator_approvals: Mapping<(AccountId, AccountId), BlockNumber>,
         /// Kitty coin contract reference
         acceptable_erc20: ink::contract_ref!(TERC20),
         /// Price for minting a kitty
         mint_price: u128,
+        /// Contract owner allowed to configure the contract.
+        owner: AccountId,
+        /// Market contract allowed to lock listed kitties.
+        market: Option<AccountId>,
+        /// Mapping from kitty to whether it is locked by the market.
+        locked_for_market: Mapping<KittyId, bool>,
+        /// Whether kitties can be migrated out of this contract.
+        migration_open: bool,
+        /// Chance in basis points for a minted kitty to be lucky.
+        lucky_bps: u16,
+        /// Mapping from kitty to whether it got the rare lucky trait.
+        lucky: Mapping<KittyId, bool>,
+        /// Multiple of the mint price charged for minting a lucky kitty.
+        lucky_price_multiplier: u32,
+        /// Maximum number of kitties that can exist at the same time.
+        max_supply: Option<u32>,
+        /// Number of kitties currently in existence.
+        minted_total: u32,
+        /// Number of kitties ever minted or bred, never decreased by burns.
+        total_minted: u64,
+        /// Number of kitties ever burned.
+        total_burned: u64,
+        /// Next id assigned by `mint_auto`.
+        next_id: KittyId,
+        /// Mapping from kitty to the only accounts it can be transferred to.
+        /// Kitties without an entry can be transferred to anyone.
+        recipient_allowlist: Mapping<KittyId, Vec<AccountId>>,
+        /// Mapping from kitty to its genes.
+        genes: Mapping<KittyId, [u8; 32]>,
+        /// Mapping from kitty to the block its breeding cooldown ends.
+        cooldown_end: Mapping<KittyId, BlockNumber>,
+        /// Mapping from kitty to its generation, minted kitties are generation 0.
+        generation: Mapping<KittyId, u16>,
+        /// Account receiving royalties on kitty sales.
+        royalty_receiver: AccountId,
+        /// Royalty on kitty sales in basis points.
+        royalty_basis_points: u16,
+        /// Whether minting, transfers and approvals are stopped.
+        paused: bool,
+        /// Price for breeding two generation 0 kitties.
+        base_breed_fee: u128,
+        /// How much each generation of the older parent adds to the breeding price,
+        /// as a multiple of `base_breed_fee`.
+        generation_factor: u32,
+        /// Maximum number of kitties a single account can own.
+        max_owned: Option<u32>,
+        /// Accounts that have ever held a kitty.
+        is_holder: Mapping<AccountId, ()>,
+        /// A mapping from index to holder, in the order the holders first got a kitty.
+        holders: Mapping<u32, AccountId>,
+        /// Number of accounts that have ever held a kitty.
+        holder_count: u32,
+        /// ERC-20 token burned for every minted kitty, `None` disables crafting.
+        ingredient_token: Option<AccountId>,
+        /// Amount of the ingredient token burned for every minted kitty.
+        ingredient_amount: u128,
+        /// Contract notified after every transfer, e.g. for staking.
+        transfer_hook: Option<AccountId>,
+        /// Whether id 0 is reserved and can't be minted.
+        reserve_zero_id: bool,
+        /// Kitties frozen by the contract owner, which can't be transferred or burned.
+        frozen: Mapping<KittyId, ()>,
     }
 
     /// Event emitted when a kitty transfer occurs.
@@ -97,6 +216,57 @@ mod kitties {
         id: KittyId,
     }
 
+    /// Event emitted when the mint price changes.
+    #[ink(event)]
+    pub struct MintPriceChanged {
+        old: u128,
+        new: u128,
+    }
+
+    /// Event emitted when a kitty is minted, with the price charged for it.
+    #[ink(event)]
+    pub struct Minted {
+        #[ink(topic)]
+        to: AccountId,
+        #[ink(topic)]
+        id: KittyId,
+        price: u128,
+    }
+
+    /// Event emitted when a kitty is born from breeding.
+    #[ink(event)]
+    pub struct Birth {
+        #[ink(topic)]
+        child: KittyId,
+        parent1: KittyId,
+        parent2: KittyId,
+    }
+
+    /// Event emitted when the contract ownership changes.
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        previous: AccountId,
+        #[ink(topic)]
+        new: AccountId,
+    }
+
+    /// Event emitted once for all kitties removed by a migration.
+    #[ink(event)]
+    pub struct MigrationBatch {
+        ids: Vec<KittyId>,
+    }
+
+    /// Event emitted when the transfer hook fails to handle the transfer of kitty `id`.
+    /// The transfer goes through anyway.
+    #[ink(event)]
+    pub struct TransferHookFailed {
+        #[ink(topic)]
+        hook: AccountId,
+        #[ink(topic)]
+        id: KittyId,
+    }
+
     /// Event emitted when an operator is enabled or disabled for an owner.
     /// The operator can manage all NFTs of the owner.
     #[ink(event)]
@@ -110,515 +280,3529 @@ mod kitties {
 
     impl Kitties {
         /// Creates a new Kitties ERC-721 token contract.
+        ///
+        /// With a `max_supply` no more than that many kitties can exist at the same time.
+        /// Kitty sales owe `royalty_receiver` a royalty of `royalty_basis_points`, capped
+        /// at 10000. With `reserve_zero_id` kitty id 0 can't be minted.
         #[ink(constructor)]
-        pub fn new(erc20: AccountId, mint_price: u128) -> Self {
+        pub fn new(
+            erc20: AccountId,
+            mint_price: u128,
+            max_supply: Option<u32>,
+            royalty_receiver: AccountId,
+            royalty_basis_points: u16,
+            reserve_zero_id: bool,
+        ) -> Self {
             Self {
                 acceptable_erc20: erc20.into(),
                 mint_price,
                 kitty_owner: Mapping::new(),
                 token_approvals: Mapping::new(),
                 owned_kitties_count: Mapping::new(),
+                owned_tokens: Mapping::new(),
+                owned_tokens_index: Mapping::new(),
                 operator_approvals: Mapping::new(),
-            }           
+                owner: Self::env().caller(),
+                market: None,
+                locked_for_market: Mapping::new(),
+                migration_open: false,
+                lucky_bps: 0,
+                lucky: Mapping::new(),
+                lucky_price_multiplier: 1,
+                max_supply,
+                minted_total: 0,
+                total_minted: 0,
+                total_burned: 0,
+                next_id: KittyId::from(1),
+                recipient_allowlist: Mapping::new(),
+                genes: Mapping::new(),
+                cooldown_end: Mapping::new(),
+                generation: Mapping::new(),
+                royalty_receiver,
+                royalty_basis_points: royalty_basis_points.min(10_000),
+                paused: false,
+                base_breed_fee: 0,
+                generation_factor: 0,
+                max_owned: None,
+                is_holder: Mapping::new(),
+                holders: Mapping::new(),
+                holder_count: 0,
+                ingredient_token: None,
+                ingredient_amount: 0,
+                transfer_hook: None,
+                reserve_zero_id,
+                frozen: Mapping::new(),
+            }
         }
 
-        /// Transfers kitty `id` `from` the sender to the `to` `AccountId`.
-        pub fn transfer_token_from(
-            &mut self,
-            from: &AccountId,
-            to: &AccountId,
-            id: KittyId,
-        ) -> Result<()> {
-            let caller = self.env().caller();
-            if !self.exists(id) {
-                return Err(Error::TokenNotFound);
-            };
-            if !self.approved_or_owner(Some(caller), id) {
-                return Err(Error::NotApproved);
-            };
-            self.clear_approval(id);
-            self.remove_token_from(from, id)?;
-            self.add_token_to(to, id)?;
-            self.env().emit_event(Transfer {
-                from: Some(*from),
-                to: Some(*to),
-                id,
-            });
-            Ok(())
+        /// Returns `true` if the contract implements the ERC-165 interface `interface_id`.
+        #[ink(message)]
+        pub fn supports_interface(&self, interface_id: [u8; 4]) -> bool {
+            SUPPORTED_INTERFACES.contains(&interface_id)
         }
 
-        /// Removes kitty `id` from the owner.
-        pub fn remove_token_from(&mut self, from: &AccountId, id: KittyId) -> Result<()> {
-            let Self {
-                kitty_owner,
-                owned_kitties_count,
-                ..
-            } = self;
+        /// Returns the default royalty receiver and basis points.
+        #[ink(message)]
+        pub fn default_royalty(&self) -> (AccountId, u16) {
+            (self.royalty_receiver, self.royalty_basis_points)
+        }
 
-            if !kitty_owner.contains(id) {
-                return Err(Error::TokenNotFound);
+        /// Sets the default royalty receiver and basis points.
+        #[ink(message)]
+        pub fn set_default_royalty(
+            &mut self,
+            receiver: AccountId,
+            basis_points: u16,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            if basis_points > 10_000 {
+                return Err(Error::InvalidBasisPoints);
             }
-
-            let count = owned_kitties_count
-                .get(from)
-                .map(|c| c - 1)
-                .ok_or(Error::CannotFetchValue)?;
-            owned_kitties_count.insert(from, &count);
-            kitty_owner.remove(id);
-
+            self.royalty_receiver = receiver;
+            self.royalty_basis_points = basis_points;
             Ok(())
         }
 
-        /// Adds the kitty `id` to the `to` AccountID.
-        pub fn add_token_to(&mut self, to: &AccountId, id: KittyId) -> Result<()> {
-            let Self {
-                kitty_owner,
-                owned_kitties_count,
-                ..
-            } = self;
-
-            if kitty_owner.contains(id) {
-                return Err(Error::TokenExists);
-            }
-
-            if *to == AccountId::from([0x0; 32]) {
-                return Err(Error::NotAllowed);
-            };
-
-            let count = owned_kitties_count.get(to).map(|c| c + 1).unwrap_or(1);
+        /// Returns the genes of kitty `id`.
+        #[ink(message)]
+        pub fn genes_of(&self, id: KittyId) -> Option<[u8; 32]> {
+            self.genes.get(id)
+        }
 
-            owned_kitties_count.insert(to, &count);
-            kitty_owner.insert(id, to);
+        /// Returns the generation of kitty `id`.
+        ///
+        /// Minted kitties are generation 0, a bred kitty is one generation after its
+        /// youngest parent.
+        #[ink(message)]
+        pub fn generation_of(&self, id: KittyId) -> Option<u16> {
+            self.generation.get(id)
+        }
 
-            Ok(())
+        /// Returns `true` if kitty `id` exists and its breeding cooldown has ended.
+        #[ink(message)]
+        pub fn ready_to_breed(&self, id: KittyId) -> bool {
+            self.exists(id)
+                && self.env().block_number() >= self.cooldown_end.get(id).unwrap_or_default()
         }
 
-        /// Approves or disapproves the operator to transfer all kitties of the caller.
-        pub fn approve_for_all(&mut self, to: AccountId, approved: bool) -> Result<()> {
+        /// Breeds kitties `parent1` and `parent2` into a new kitty for the caller and
+        /// returns the id of the child.
+        ///
+        /// The caller must own or be approved for both parents. The child gets the next
+        /// unused id like `mint_auto` and costs `breed_cost` instead of the mint price. The
+        /// ingredient is burned for it like for any other new kitty.
+        /// Afterwards both parents cool down before they can breed again.
+        #[ink(message)]
+        pub fn breed(&mut self, parent1: KittyId, parent2: KittyId) -> Result<KittyId> {
             let caller = self.env().caller();
-            if to == caller {
+            if !self.exists(parent1) || !self.exists(parent2) {
+                return Err(Error::TokenNotFound);
+            }
+            if parent1 == parent2 {
                 return Err(Error::NotAllowed);
             }
-            self.env().emit_event(ApprovalForAll {
-                owner: caller,
-                operator: to,
-                approved,
-            });
-
-            if approved {
-                self.operator_approvals.insert((&caller, &to), &());
-            } else {
-                self.operator_approvals.remove((&caller, &to));
+            if !self.approved_or_owner(Some(caller), parent1)
+                || !self.approved_or_owner(Some(caller), parent2)
+            {
+                return Err(Error::NotApproved);
+            }
+            if !self.ready_to_breed(parent1) || !self.ready_to_breed(parent2) {
+                return Err(Error::KittyOnCooldown);
             }
 
-            Ok(())
-        }
+            let genes1 = self.genes.get(parent1).unwrap_or_default();
+            let genes2 = self.genes.get(parent2).unwrap_or_default();
+            let genes = Self::mix_genes(&genes1, &genes2);
+            let generation = self
+                .generation_of(parent1)
+                .unwrap_or_default()
+                .max(self.generation_of(parent2).unwrap_or_default())
+                .saturating_add(1);
 
-        /// Approve the passed `AccountId` to transfer the specified kitty on behalf of
-        /// the message's sender.
-        pub fn approve_for(&mut self, to: &AccountId, id: KittyId) -> Result<()> {
-            let caller = self.env().caller();
-            let owner = self.owner_of(id);
-            if !(owner == Some(caller)
-                || self.approved_for_all(owner.expect("Error with AccountId"), caller))
-            {
-                return Err(Error::NotAllowed);
-            };
+            let cost = self.breed_cost(parent1, parent2).ok_or(Error::PriceOverflow)?;
 
-            if *to == AccountId::from([0x0; 32]) {
-                return Err(Error::NotAllowed);
-            };
+            let child = self.next_free_id()?;
+            self.charge_for_kitties(caller, 1, cost)?;
+            self.burn_ingredient(caller, 1)?;
+            self.create_token(caller, child, genes, generation)?;
+            self.next_id = Self::id_after(child)?;
 
-            if self.token_approvals.contains(id) {
-                return Err(Error::CannotInsert);
-            } else {
-                self.token_approvals.insert(id, to);
+            let now = self.env().block_number();
+            for parent in [parent1, parent2] {
+                let cooldown_end = now.saturating_add(self.cooldown_blocks(parent));
+                self.cooldown_end.insert(parent, &cooldown_end);
             }
 
-            self.env().emit_event(Approval {
-                from: caller,
-                to: *to,
-                id,
+            self.env().emit_event(Birth {
+                child,
+                parent1,
+                parent2,
             });
 
-            Ok(())
+            Ok(child)
         }
 
-        /// Removes existing approval from kitty `id`.
-        pub fn clear_approval(&mut self, id: KittyId) {
-            self.token_approvals.remove(id);
+        /// Returns the price for breeding `parent1` and `parent2`.
+        ///
+        /// The price is `base_breed_fee * (1 + generation_factor * generation)` where
+        /// `generation` is the higher generation of the parents. Returns `None` if a parent
+        /// doesn't exist or the price overflows.
+        #[ink(message)]
+        pub fn breed_cost(&self, parent1: KittyId, parent2: KittyId) -> Option<u128> {
+            let generation = self.generation_of(parent1)?.max(self.generation_of(parent2)?);
+            u128::from(self.generation_factor)
+                .checked_mul(generation.into())?
+                .checked_add(1)?
+                .checked_mul(self.base_breed_fee)
         }
 
-        // Returns the total number of kitties from an account.
-        pub fn balance_of_or_zero(&self, of: &AccountId) -> u32 {
-            self.owned_kitties_count.get(of).unwrap_or(0)
+        /// Returns the base breeding price and the generation factor.
+        #[ink(message)]
+        pub fn breed_fee(&self) -> (u128, u32) {
+            (self.base_breed_fee, self.generation_factor)
         }
 
-        /// Gets an operator on other Account's behalf.
-        pub fn approved_for_all(&self, owner: AccountId, operator: AccountId) -> bool {
-            self.operator_approvals.contains((&owner, &operator))
+        /// Returns the ingredient token burned for every minted kitty and the amount burned.
+        #[ink(message)]
+        pub fn ingredient(&self) -> (Option<AccountId>, u128) {
+            (self.ingredient_token, self.ingredient_amount)
         }
 
-        /// Returns true if the `AccountId` `from` is the owner of kitty `id`
-        /// or it has been approved on behalf of the kitty `id` owner.
-        pub fn approved_or_owner(&self, from: Option<AccountId>, id: KittyId) -> bool {
-            let owner = self.owner_of(id);
-            from != Some(AccountId::from([0x0; 32]))
-                && (from == owner
-                    || from == self.token_approvals.get(id)
-                    || self.approved_for_all(
-                        owner.expect("Error with AccountId"),
-                        from.expect("Error with AccountId"),
-                    ))
+        /// Sets the ingredient token burned for every minted kitty and the amount burned.
+        /// `None` disables crafting.
+        #[ink(message)]
+        pub fn set_ingredient(
+            &mut self,
+            ingredient_token: Option<AccountId>,
+            ingredient_amount: u128,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            self.ingredient_token = ingredient_token;
+            self.ingredient_amount = ingredient_amount;
+            Ok(())
         }
 
-        /// Returns true if kitty `id` exists or false if it does not.
-        pub fn exists(&self, id: KittyId) -> bool {
-            self.kitty_owner.contains(id)
+        /// Sets the base breeding price and the generation factor.
+        #[ink(message)]
+        pub fn set_breed_fee(
+            &mut self,
+            base_breed_fee: u128,
+            generation_factor: u32,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            self.base_breed_fee = base_breed_fee;
+            self.generation_factor = generation_factor;
+            Ok(())
         }
-    }
 
-    impl TERC721 for Kitties {
-        /// Returns the balance of the owner.
-        ///
-        /// This represents the amount of unique kitties the owner has.
+        /// Returns the only accounts kitty `id` can be transferred to.
+        /// An empty list means the kitty can be transferred to anyone.
         #[ink(message)]
-        fn balance_of(&self, owner: AccountId) -> u32 {
-            self.balance_of_or_zero(&owner)
+        pub fn recipient_allowlist(&self, id: KittyId) -> Vec<AccountId> {
+            self.recipient_allowlist.get(id).unwrap_or_default()
         }
 
-        /// Returns the owner of the kitty.
+        /// Restricts the accounts kitty `id` can be transferred to. An empty list lifts
+        /// the restriction. Only the kitty owner or the contract owner can call this.
         #[ink(message)]
-        fn owner_of(&self, id: KittyId) -> Option<AccountId> {
-            self.kitty_owner.get(id)
+        pub fn set_recipient_allowlist(
+            &mut self,
+            id: KittyId,
+            recipients: Vec<AccountId>,
+        ) -> Result<()> {
+            let caller = self.env().caller();
+            let owner = self.owner_of(id).ok_or(Error::TokenNotFound)?;
+            if caller != owner && caller != self.owner {
+                return Err(Error::NotOwner);
+            }
+
+            if recipients.is_empty() {
+                self.recipient_allowlist.remove(id);
+            } else {
+                self.recipient_allowlist.insert(id, &recipients);
+            }
+
+            Ok(())
         }
 
-        /// Returns the approved account ID for this kitty if any.
+        /// Returns `true` if kitty id 0 is reserved and can't be minted.
         #[ink(message)]
-        fn get_approved(&self, id: KittyId) -> Option<AccountId> {
-            self.token_approvals.get(id)
+        pub fn zero_id_reserved(&self) -> bool {
+            self.reserve_zero_id
         }
 
-        /// Returns `true` if the operator is approved by the owner.
+        /// Returns the price for minting a kitty.
         #[ink(message)]
-        fn is_approved_for_all(&self, owner: AccountId, operator: AccountId) -> bool {
-            self.approved_for_all(owner, operator)
+        pub fn mint_price(&self) -> u128 {
+            self.mint_price
         }
 
-        /// Approves or disapproves the operator for all kitties of the caller.
+        /// Returns how many kitties the payment token allowance of `who` to this contract
+        /// covers at the current mint price, `u32::MAX` if minting is free. Lucky kitties
+        /// cost more, so fewer of them are covered.
         #[ink(message)]
-        fn set_approval_for_all(&mut self, to: AccountId, approved: bool) -> Result<()> {
-            self.approve_for_all(to, approved)?;
-            Ok(())
+        pub fn remaining_mints(&self, who: AccountId) -> u32 {
+            if self.mint_price == 0 {
+                return u32::MAX;
+            }
+            let allowance = self.acceptable_erc20.allowance(who, self.env().account_id());
+            u32::try_from(allowance / self.mint_price).unwrap_or(u32::MAX)
         }
 
-        /// Approves the account to transfer the specified kitty on behalf of the caller.
+        /// Sets the price for minting a kitty.
         #[ink(message)]
-        fn approve(&mut self, to: AccountId, id: KittyId) -> Result<()> {
-            self.approve_for(&to, id)?;
+        pub fn set_mint_price(&mut self, new_price: u128) -> Result<()> {
+            self.ensure_owner()?;
+
+            let old = self.mint_price;
+            self.mint_price = new_price;
+
+            self.env().emit_event(MintPriceChanged {
+                old,
+                new: new_price,
+            });
+
             Ok(())
         }
 
-        /// Transfers the kitty from the caller to the given destination.
+        /// Transfers `amount` of the collected mint revenue to the contract owner.
         #[ink(message)]
-        fn transfer(&mut self, destination: AccountId, id: KittyId) -> Result<()> {
-            let caller = self.env().caller();
-            self.transfer_token_from(&caller, &destination, id)?;
+        pub fn withdraw(&mut self, amount: Balance) -> Result<()> {
+            self.ensure_owner()?;
+
+            let withdraw_result = self.acceptable_erc20.transfer(self.owner, amount);
+            if withdraw_result.is_err() {
+                return Err(Error::CoinTransferFail);
+            }
+
             Ok(())
         }
 
-        /// Transfer approved or owned kitty.
+        /// Creates a new kitty with the next unused id and returns that id.
+        ///
+        /// Ids are assigned in increasing order starting from 1, skipping ids that were
+        /// already minted with `mint`.
         #[ink(message)]
-        fn transfer_from(&mut self, from: AccountId, to: AccountId, id: KittyId) -> Result<()> {
-            self.transfer_token_from(&from, &to, id)?;
-            Ok(())
+        pub fn mint_auto(&mut self) -> Result<KittyId> {
+            let id = self.next_free_id()?;
+
+            self.mint_token(self.env().caller(), id, self.pseudo_random(id), 0)?;
+            self.next_id = Self::id_after(id)?;
+
+            Ok(id)
         }
 
-        /// Creates a new kitty.
+        /// Creates kitties `ids` for the caller, charging the mint price for all of them
+        /// in a single payment.
+        ///
+        /// Fails without minting any kitty if one of the ids exists or appears twice.
         #[ink(message)]
-        fn mint(&mut self, id: KittyId) -> Result<()> {
+        pub fn mint_batch(&mut self, ids: Vec<KittyId>) -> Result<()> {
+            for (i, id) in ids.iter().enumerate() {
+                if self.exists(*id) || ids[..i].contains(id) {
+                    return Err(Error::TokenExists);
+                }
+                if *id == KittyId::from(0) && self.reserve_zero_id {
+                    return Err(Error::NotAllowed);
+                }
+            }
             let caller = self.env().caller();
-            let kitties_account = self.env().account_id().into();
+            let count = u32::try_from(ids.len()).map_err(|_| Error::MaxSupplyReached)?;
+            self.ensure_can_hold(&caller, count)?;
 
-            let payment_result = self.acceptable_erc20.transfer_from(caller, kitties_account, self.mint_price);
-            if payment_result.is_err() {
-                return Err(Error::CoinTransferFail);
+            let prices = self.charge_mint_price(caller, &ids)?;
+            for (id, price) in ids.into_iter().zip(prices) {
+                self.create_token(caller, id, self.pseudo_random(id), 0)?;
+                self.env().emit_event(Minted {
+                    to: caller,
+                    id,
+                    price,
+                });
             }
 
-            self.add_token_to(&caller, id)?;
-
-            self.env().emit_event(Transfer {
-                from: Some(AccountId::from([0x0; 32])),
-                to: Some(caller),
-                id,
-            });
             Ok(())
         }
 
-        /// Deletes an existing kitty. Only the owner can burn the kitty.
+        /// Transfers kitties `ids` from the caller to `to`.
+        ///
+        /// Fails without transferring any kitty if one of them can't be transferred.
         #[ink(message)]
-        fn burn(&mut self, id: KittyId) -> Result<()> {
+        pub fn transfer_batch(&mut self, to: AccountId, ids: Vec<KittyId>) -> Result<()> {
             let caller = self.env().caller();
-            let Self {
-                kitty_owner,
-                owned_kitties_count,
-                ..
-            } = self;
-
-            let owner = kitty_owner.get(id).ok_or(Error::TokenNotFound)?;
-            if owner != caller {
-                return Err(Error::NotOwner);
-            };
-
-            let count = owned_kitties_count
-                .get(caller)
-                .map(|c| c - 1)
-                .ok_or(Error::CannotFetchValue)?;
-            owned_kitties_count.insert(caller, &count);
-            kitty_owner.remove(id);
+            for &id in &ids {
+                self.ensure_can_transfer(&caller, &to, id)?;
+            }
+            if to != caller {
+                let count = u32::try_from(ids.len()).map_err(|_| Error::HoldingLimitReached)?;
+                self.ensure_can_hold(&to, count)?;
+            }
 
-            self.env().emit_event(Transfer {
-                from: Some(caller),
-                to: Some(AccountId::from([0x0; 32])),
-                id,
-            });
+            for id in ids {
+                self.transfer_token_from(&caller, &to, id)?;
+            }
 
             Ok(())
         }
+
+        /// Transfers every kitty of `entries` from the caller to its paired recipient.
+        ///
+        /// Fails without transferring any kitty if one of them can't be transferred or
+        /// is sent to the zero address.
+        #[ink(message)]
+        pub fn transfer_distribution(&mut self, entries: Vec<(KittyId, AccountId)>) -> Result<()> {
+            let caller = self.env().caller();
+            for (id, to) in &entries {
+                if *to == AccountId::from([0x0; 32]) {
+                    return Err(Error::NotAllowed);
+                }
+                self.ensure_can_transfer(&caller, to, *id)?;
+                if *to != caller {
+                    let count = entries.iter().filter(|(_, other)| other == to).count();
+                    let count = u32::try_from(count).map_err(|_| Error::HoldingLimitReached)?;
+                    self.ensure_can_hold(to, count)?;
+                }
+            }
+
+            for (id, to) in entries {
+                self.transfer_token_from(&caller, &to, id)?;
+            }
+
+            Ok(())
+        }
+
+        /// Returns the maximum number of kitties that can exist at the same time.
+        #[ink(message)]
+        pub fn max_supply(&self) -> Option<u32> {
+            self.max_supply
+        }
+
+        /// Returns the number of kitties minted so far, existing or burned, and the cap.
+        ///
+        /// Burned kitties still count as minted here even though they free up their slot
+        /// under the cap.
+        #[ink(message)]
+        pub fn mint_progress(&self) -> (u32, Option<u32>) {
+            let burned = u32::try_from(self.total_burned).unwrap_or(u32::MAX);
+            (self.minted_total.saturating_add(burned), self.max_supply)
+        }
+
+        /// Returns the maximum number of kitties a single account can own.
+        #[ink(message)]
+        pub fn max_owned(&self) -> Option<u32> {
+            self.max_owned
+        }
+
+        /// Sets the maximum number of kitties a single account can own.
+        /// `None` means unlimited.
+        #[ink(message)]
+        pub fn set_max_owned(&mut self, max_owned: Option<u32>) -> Result<()> {
+            self.ensure_owner()?;
+            self.max_owned = max_owned;
+            Ok(())
+        }
+
+        /// Returns the number of kitties currently in existence.
+        #[ink(message)]
+        pub fn total_supply(&self) -> u32 {
+            self.minted_total
+        }
+
+        /// Returns the number of kitties ever minted or bred, including burned ones.
+        #[ink(message)]
+        pub fn total_minted(&self) -> u64 {
+            self.total_minted
+        }
+
+        /// Returns the number of kitties ever burned. Kitties migrated out of this
+        /// contract are not counted, without migrations `total_minted - total_burned`
+        /// is the current supply.
+        #[ink(message)]
+        pub fn total_burned(&self) -> u64 {
+            self.total_burned
+        }
+
+        /// Returns the kitty of `owner` at `index`, `None` if `index` is not below its
+        /// balance. The order changes when kitties leave the owner.
+        #[ink(message)]
+        pub fn token_of_owner_by_index(&self, owner: AccountId, index: u32) -> Option<KittyId> {
+            self.owned_tokens.get((owner, index))
+        }
+
+        /// Returns up to `limit` kitties of `owner` starting at index `start`, in the
+        /// order of `token_of_owner_by_index`.
+        ///
+        /// `limit` is clamped to `MAX_PAGE_SIZE`.
+        #[ink(message)]
+        pub fn tokens_of_owner(&self, owner: AccountId, start: u32, limit: u32) -> Vec<KittyId> {
+            let end = start
+                .saturating_add(limit.min(MAX_PAGE_SIZE))
+                .min(self.balance_of(owner));
+            (start..end)
+                .filter_map(|index| self.owned_tokens.get((owner, index)))
+                .collect()
+        }
+
+        /// Returns up to `limit` accounts owning the most kitties with their balances,
+        /// sorted by balance in descending order.
+        ///
+        /// Only the first `MAX_HOLDER_SCAN` accounts that ever held a kitty are considered
+        /// to bound the gas cost.
+        #[ink(message)]
+        pub fn top_holders(&self, limit: u32) -> Vec<(AccountId, u32)> {
+            let mut holders: Vec<(AccountId, u32)> = (0..self.holder_count)
+                .take(MAX_HOLDER_SCAN)
+                .filter_map(|index| self.holders.get(index))
+                .map(|holder| (holder, self.balance_of(holder)))
+                .filter(|&(_, balance)| balance > 0)
+                .collect();
+            holders.sort_by_key(|&(_, balance)| core::cmp::Reverse(balance));
+            holders.truncate(limit as usize);
+            holders
+        }
+
+        /// Returns the Merkle root over the `(id, owner)` leaves of kitties `ids`, in the
+        /// given order.
+        ///
+        /// Every leaf is the Blake2x256 hash of the SCALE encoded id and owner, `None` for
+        /// kitties that don't exist. Every level hashes pairs of nodes concatenated, an odd
+        /// last node moves up unchanged. Without ids the root is all zeros.
+        #[ink(message)]
+        pub fn ownership_root(&self, ids: Vec<KittyId>) -> [u8; 32] {
+            let mut nodes: Vec<[u8; 32]> = ids
+                .into_iter()
+                .map(|id| {
+                    let mut leaf = <Blake2x256 as HashOutput>::Type::default();
+                    ink::env::hash_encoded::<Blake2x256, _>(&(id, self.owner_of(id)), &mut leaf);
+                    leaf
+                })
+                .collect();
+
+            while nodes.len() > 1 {
+                nodes = nodes
+                    .chunks(2)
+                    .map(|pair| match pair {
+                        [left, right] => {
+                            let mut node = <Blake2x256 as HashOutput>::Type::default();
+                            ink::env::hash_encoded::<Blake2x256, _>(&(left, right), &mut node);
+                            node
+                        }
+                        _ => pair[0],
+                    })
+                    .collect();
+            }
+
+            nodes.first().copied().unwrap_or_default()
+        }
+
+        /// Returns the chance in basis points for a minted kitty to be lucky.
+        #[ink(message)]
+        pub fn lucky_bps(&self) -> u16 {
+            self.lucky_bps
+        }
+
+        /// Sets the chance in basis points for a minted kitty to be lucky.
+        /// Zero disables lucky mints.
+        #[ink(message)]
+        pub fn set_lucky_bps(&mut self, lucky_bps: u16) -> Result<()> {
+            self.ensure_owner()?;
+            if lucky_bps > 10_000 {
+                return Err(Error::InvalidBasisPoints);
+            }
+            self.lucky_bps = lucky_bps;
+            Ok(())
+        }
+
+        /// Returns the multiple of the mint price charged for minting a lucky kitty.
+        #[ink(message)]
+        pub fn lucky_price_multiplier(&self) -> u32 {
+            self.lucky_price_multiplier
+        }
+
+        /// Sets the multiple of the mint price charged for minting a lucky kitty.
+        /// Zero is rejected, one charges lucky kitties the plain mint price.
+        ///
+        /// Lucky is the only rarity tier, so this single multiplier is the whole table.
+        #[ink(message)]
+        pub fn set_lucky_price_multiplier(&mut self, multiplier: u32) -> Result<()> {
+            self.ensure_owner()?;
+            if multiplier == 0 {
+                return Err(Error::NotAllowed);
+            }
+            self.lucky_price_multiplier = multiplier;
+            Ok(())
+        }
+
+        /// Returns the price for minting kitty `id` in the current block, which is the
+        /// mint price times the lucky price multiplier if the kitty would be lucky.
+        #[ink(message)]
+        pub fn mint_price_of(&self, id: KittyId) -> u128 {
+            if self.roll_lucky(id) {
+                self.mint_price.saturating_mul(self.lucky_price_multiplier.into())
+            } else {
+                self.mint_price
+            }
+        }
+
+        /// Creates kitty `id` like `mint`, failing if minting it costs more than
+        /// `max_price` because it would be lucky.
+        #[ink(message)]
+        pub fn mint_with_max_price(&mut self, id: KittyId, max_price: u128) -> Result<()> {
+            if self.mint_price_of(id) > max_price {
+                return Err(Error::PriceExceedsMax);
+            }
+            self.mint_token(self.env().caller(), id, self.pseudo_random(id), 0)
+        }
+
+        /// Returns `true` if kitty `id` got the rare lucky trait when minted.
+        #[ink(message)]
+        pub fn is_lucky(&self, id: KittyId) -> bool {
+            self.lucky.get(id).unwrap_or(false)
+        }
+
+        /// Returns pseudo random bytes derived from the current block, the caller and `id`.
+        ///
+        /// The block author can influence this value, so it must not guard anything of value.
+        pub fn pseudo_random(&self, id: KittyId) -> [u8; 32] {
+            let mut output = <Blake2x256 as HashOutput>::Type::default();
+            ink::env::hash_encoded::<Blake2x256, _>(
+                &(
+                    self.env().block_number(),
+                    self.env().block_timestamp(),
+                    self.env().caller(),
+                    id,
+                ),
+                &mut output,
+            );
+            output
+        }
+
+        /// Mixes the genes of two parents into the genes of their child.
+        ///
+        /// Every byte comes from one of the parents, chosen by a hash of both parents' genes,
+        /// and then gets mutated by that hash. The same parents always give the same child.
+        pub fn mix_genes(genes1: &[u8; 32], genes2: &[u8; 32]) -> [u8; 32] {
+            let mut mask = <Blake2x256 as HashOutput>::Type::default();
+            ink::env::hash_encoded::<Blake2x256, _>(&(genes1, genes2), &mut mask);
+
+            let mut genes = [0u8; 32];
+            for (i, gene) in genes.iter_mut().enumerate() {
+                let inherited = if mask[i] & 1 == 0 { genes1[i] } else { genes2[i] };
+                *gene = inherited ^ (mask[i] & 0b1000_0001);
+            }
+            genes
+        }
+
+        /// Returns the number of blocks kitty `id` cools down for after breeding.
+        ///
+        /// Higher generations cool down longer, up to the last entry of the cooldown table.
+        pub fn cooldown_blocks(&self, id: KittyId) -> BlockNumber {
+            let generation = usize::from(self.generation_of(id).unwrap_or_default());
+            BREEDING_COOLDOWNS[generation.min(BREEDING_COOLDOWNS.len() - 1)]
+        }
+
+        /// Returns the next id not taken by an existing kitty, starting at `next_id`.
+        pub fn next_free_id(&self) -> Result<KittyId> {
+            let mut id = self.next_id;
+            while self.exists(id) {
+                id = Self::id_after(id)?;
+            }
+            Ok(id)
+        }
+
+        /// Returns the id following `id`, failing once the ids are used up.
+        fn id_after(id: KittyId) -> Result<KittyId> {
+            u32::from(id)
+                .checked_add(1)
+                .map(KittyId::from)
+                .ok_or(Error::MaxSupplyReached)
+        }
+
+        /// Rolls whether a newly minted kitty `id` is lucky.
+        pub fn roll_lucky(&self, id: KittyId) -> bool {
+            if self.lucky_bps == 0 {
+                return false;
+            }
+            let random = self.pseudo_random(id);
+            u16::from_le_bytes([random[0], random[1]]) % 10_000 < self.lucky_bps
+        }
+
+        /// Returns the contract owner.
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            self.owner
+        }
+
+        /// Hands the contract ownership over to `new_owner`.
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            if new_owner == AccountId::from([0x0; 32]) {
+                return Err(Error::NotAllowed);
+            }
+
+            let previous = self.owner;
+            self.owner = new_owner;
+
+            self.env().emit_event(OwnershipTransferred {
+                previous,
+                new: new_owner,
+            });
+
+            Ok(())
+        }
+
+        /// Returns `true` if minting, transfers and approvals are stopped.
+        #[ink(message)]
+        pub fn paused(&self) -> bool {
+            self.paused
+        }
+
+        /// Stops or resumes minting, transfers and approvals in an emergency.
+        #[ink(message)]
+        pub fn set_paused(&mut self, paused: bool) -> Result<()> {
+            self.ensure_owner()?;
+            self.paused = paused;
+            Ok(())
+        }
+
+        /// Returns `true` if kitty `id` is frozen and can't be transferred or burned.
+        #[ink(message)]
+        pub fn is_frozen(&self, id: KittyId) -> bool {
+            self.frozen.contains(id)
+        }
+
+        /// Freezes kitty `id` so nobody can transfer or burn it, e.g. during a dispute.
+        /// Only the contract owner can call this.
+        #[ink(message)]
+        pub fn freeze_token(&mut self, id: KittyId) -> Result<()> {
+            self.ensure_owner()?;
+            if !self.exists(id) {
+                return Err(Error::TokenNotFound);
+            }
+            self.frozen.insert(id, &());
+            Ok(())
+        }
+
+        /// Lets frozen kitty `id` be transferred and burned again. Only the contract owner
+        /// can call this.
+        #[ink(message)]
+        pub fn unfreeze_token(&mut self, id: KittyId) -> Result<()> {
+            self.ensure_owner()?;
+            self.frozen.remove(id);
+            Ok(())
+        }
+
+        /// Returns the market contract allowed to lock listed kitties.
+        #[ink(message)]
+        pub fn market(&self) -> Option<AccountId> {
+            self.market
+        }
+
+        /// Sets the market contract allowed to lock listed kitties.
+        #[ink(message)]
+        pub fn set_market(&mut self, market: Option<AccountId>) -> Result<()> {
+            self.ensure_owner()?;
+            self.market = market;
+            Ok(())
+        }
+
+        /// Returns the contract notified after every transfer.
+        #[ink(message)]
+        pub fn transfer_hook(&self) -> Option<AccountId> {
+            self.transfer_hook
+        }
+
+        /// Sets the contract notified after every transfer, `None` disables it.
+        #[ink(message)]
+        pub fn set_transfer_hook(&mut self, transfer_hook: Option<AccountId>) -> Result<()> {
+            self.ensure_owner()?;
+            self.transfer_hook = transfer_hook;
+            Ok(())
+        }
+
+        /// Returns `true` if kitty `id` is locked by the market.
+        #[ink(message)]
+        pub fn is_locked_for_market(&self, id: KittyId) -> bool {
+            self.locked_for_market.get(id).unwrap_or(false)
+        }
+
+        /// Returns `true` if kitties can be migrated out of this contract.
+        #[ink(message)]
+        pub fn migration_open(&self) -> bool {
+            self.migration_open
+        }
+
+        /// Opens or closes the migration out of this contract.
+        #[ink(message)]
+        pub fn set_migration_open(&mut self, open: bool) -> Result<()> {
+            self.ensure_owner()?;
+            self.migration_open = open;
+            Ok(())
+        }
+
+        /// Removes kitties `ids` from their owners while migrating to a new contract.
+        ///
+        /// Unlike `burn`, no `Transfer` event is emitted per kitty, a single
+        /// `MigrationBatch` event covers the whole batch. The market and the transfer hook
+        /// are still told about every kitty. Fails if any of the kitties is frozen.
+        #[ink(message)]
+        pub fn migrate_out(&mut self, ids: Vec<KittyId>) -> Result<()> {
+            self.ensure_owner()?;
+            if !self.migration_open {
+                return Err(Error::MigrationClosed);
+            }
+
+            for &id in &ids {
+                let owner = self.kitty_owner.get(id).ok_or(Error::TokenNotFound)?;
+                if self.is_frozen(id) {
+                    return Err(Error::TokenFrozen);
+                }
+                self.remove_token(&owner, id)?;
+            }
+
+            self.env().emit_event(MigrationBatch { ids });
+
+            Ok(())
+        }
+
+        /// Returns an error if the caller is not the contract owner.
+        pub fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotContractOwner);
+            }
+            Ok(())
+        }
+
+        /// Returns an error if the contract is paused.
+        pub fn ensure_not_paused(&self) -> Result<()> {
+            if self.paused {
+                return Err(Error::ContractPaused);
+            }
+            Ok(())
+        }
+
+        /// Returns an error if kitty `id` is locked and the caller is not the market.
+        pub fn ensure_not_locked(&self, id: KittyId) -> Result<()> {
+            if self.is_locked_for_market(id) && Some(self.env().caller()) != self.market {
+                return Err(Error::LockedForMarket);
+            }
+            Ok(())
+        }
+
+        /// Transfers kitty `id` `from` the sender to the `to` `AccountId`.
+        ///
+        /// A transfer to the owner itself is still checked and clears the approval, but
+        /// leaves the kitty counts untouched.
+        pub fn transfer_token_from(
+            &mut self,
+            from: &AccountId,
+            to: &AccountId,
+            id: KittyId,
+        ) -> Result<()> {
+            self.ensure_can_transfer(from, to, id)?;
+            self.clear_approval(id);
+            self.locked_for_market.remove(id);
+            if from != to {
+                self.remove_token_from(from, id)?;
+                self.add_token_to(to, id)?;
+            }
+            self.env().emit_event(Transfer {
+                from: Some(*from),
+                to: Some(*to),
+                id,
+            });
+            self.notify_market(from, to, id);
+            self.notify_transfer_hook(from, to, id);
+            Ok(())
+        }
+
+        /// Tells the market that kitty `id` moved, unless the market moved it itself.
+        /// `to` is the zero address for a burned kitty.
+        /// A failing hook does not block the transfer.
+        fn notify_market(&self, from: &AccountId, to: &AccountId, id: KittyId) {
+            let Some(market) = self.market else {
+                return;
+            };
+            if self.env().caller() == market {
+                return;
+            }
+
+            let mut market: ink::contract_ref!(TKittyTransferHook) = market.into();
+            let _ = market
+                .call_mut()
+                .on_kitty_transferred(id, *from, *to)
+                .try_invoke();
+        }
+
+        /// Tells the transfer hook that kitty `id` moved or was burned. A failing hook does not block
+        /// the transfer, it is reported with a `TransferHookFailed` event.
+        fn notify_transfer_hook(&self, from: &AccountId, to: &AccountId, id: KittyId) {
+            let Some(hook) = self.transfer_hook else {
+                return;
+            };
+
+            let mut transfer_hook: ink::contract_ref!(TKittyTransferHook) = hook.into();
+            let response = transfer_hook
+                .call_mut()
+                .on_kitty_transferred(id, *from, *to)
+                .try_invoke();
+            if !matches!(response, Ok(Ok(()))) {
+                self.env().emit_event(TransferHookFailed { hook, id });
+            }
+        }
+
+        /// Destroys kitty `id` of `owner` with everything stored about it.
+        ///
+        /// Fails if the kitty is frozen.
+        pub fn burn_token(&mut self, owner: &AccountId, id: KittyId) -> Result<()> {
+            if self.is_frozen(id) {
+                return Err(Error::TokenFrozen);
+            }
+            self.clear_approval(id);
+            self.remove_token(owner, id)?;
+            self.total_burned += 1;
+
+            self.env().emit_event(Transfer {
+                from: Some(*owner),
+                to: Some(AccountId::from([0x0; 32])),
+                id,
+            });
+
+            Ok(())
+        }
+
+        /// Removes kitty `id` from `owner` with everything stored about it and tells the
+        /// market and the transfer hook it is gone. Emitting events is up to the caller.
+        fn remove_token(&mut self, owner: &AccountId, id: KittyId) -> Result<()> {
+            self.token_approvals.remove(id);
+            self.locked_for_market.remove(id);
+            self.lucky.remove(id);
+            self.recipient_allowlist.remove(id);
+            self.genes.remove(id);
+            self.cooldown_end.remove(id);
+            self.generation.remove(id);
+            self.frozen.remove(id);
+            self.remove_token_from(owner, id)?;
+            self.minted_total -= 1;
+
+            let zero = AccountId::from([0x0; 32]);
+            self.notify_market(owner, &zero, id);
+            self.notify_transfer_hook(owner, &zero, id);
+
+            Ok(())
+        }
+
+        /// Returns an error if `to` would own more than `max_owned` kitties after receiving
+        /// `count` more.
+        pub fn ensure_can_hold(&self, to: &AccountId, count: u32) -> Result<()> {
+            if let Some(max_owned) = self.max_owned {
+                if self.balance_of(*to).saturating_add(count) > max_owned {
+                    return Err(Error::HoldingLimitReached);
+                }
+            }
+            Ok(())
+        }
+
+        /// Returns an error if the caller can't transfer kitty `id` from `from` to `to`.
+        pub fn ensure_can_transfer(
+            &self,
+            from: &AccountId,
+            to: &AccountId,
+            id: KittyId,
+        ) -> Result<()> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            if !self.exists(id) {
+                return Err(Error::TokenNotFound);
+            };
+            if self.is_frozen(id) {
+                return Err(Error::TokenFrozen);
+            }
+            if !self.approved_or_owner(Some(caller), id) {
+                return Err(Error::NotApproved);
+            };
+            if self.owner_of(id) != Some(*from) {
+                return Err(Error::NotOwner);
+            }
+            self.ensure_not_locked(id)?;
+            if let Some(recipients) = self.recipient_allowlist.get(id) {
+                if !recipients.contains(to) {
+                    return Err(Error::RecipientNotAllowed);
+                }
+            }
+            if to != from {
+                self.ensure_can_hold(to, 1)?;
+            }
+            Ok(())
+        }
+
+        /// Calls `on_erc721_received` on `to` if it is a contract and checks that the
+        /// kitty was accepted.
+        pub fn check_on_erc721_received(
+            &self,
+            from: &AccountId,
+            to: &AccountId,
+            id: KittyId,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            if !self.env().is_contract(to) {
+                return Ok(());
+            }
+
+            let mut receiver: ink::contract_ref!(TERC721Receiver) = (*to).into();
+            let response = receiver
+                .call_mut()
+                .on_erc721_received(self.env().caller(), *from, id, data)
+                .try_invoke();
+
+            match response {
+                Ok(Ok(magic)) if magic == ON_ERC721_RECEIVED => Ok(()),
+                _ => Err(Error::SafeTransferCheckFailed),
+            }
+        }
+
+        /// Removes kitty `id` from the owner. Fails if `from` does not own the kitty.
+        pub fn remove_token_from(&mut self, from: &AccountId, id: KittyId) -> Result<()> {
+            let Self {
+                kitty_owner,
+                owned_kitties_count,
+                owned_tokens,
+                owned_tokens_index,
+                ..
+            } = self;
+
+            let owner = kitty_owner.get(id).ok_or(Error::TokenNotFound)?;
+            if owner != *from {
+                return Err(Error::NotOwner);
+            }
+
+            let count = owned_kitties_count
+                .get(from)
+                .and_then(|c| c.checked_sub(1))
+                .ok_or(Error::CannotFetchValue)?;
+            owned_kitties_count.insert(from, &count);
+            kitty_owner.remove(id);
+
+            // Move the last kitty of the owner into the slot of the removed one.
+            let index = owned_tokens_index.take(id).unwrap_or_default();
+            if let Some(last) = owned_tokens.take((from, count)) {
+                if last != id {
+                    owned_tokens.insert((from, index), &last);
+                    owned_tokens_index.insert(last, &index);
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Adds the kitty `id` to the `to` AccountID.
+        pub fn add_token_to(&mut self, to: &AccountId, id: KittyId) -> Result<()> {
+            self.ensure_can_hold(to, 1)?;
+            let Self {
+                kitty_owner,
+                owned_kitties_count,
+                owned_tokens,
+                owned_tokens_index,
+                ..
+            } = self;
+
+            if kitty_owner.contains(id) {
+                return Err(Error::TokenExists);
+            }
+
+            if *to == AccountId::from([0x0; 32]) {
+                return Err(Error::NotAllowed);
+            };
+
+            let count = owned_kitties_count.get(to).map(|c| c + 1).unwrap_or(1);
+
+            owned_kitties_count.insert(to, &count);
+            kitty_owner.insert(id, to);
+            owned_tokens.insert((to, count - 1), &id);
+            owned_tokens_index.insert(id, &(count - 1));
+            if !self.is_holder.contains(to) {
+                self.is_holder.insert(to, &());
+                self.holders.insert(self.holder_count, to);
+                self.holder_count += 1;
+            }
+
+            Ok(())
+        }
+
+        /// Charges `minter` the mint price and creates kitty `id` of `generation` with
+        /// `genes` for it.
+        ///
+        /// Nothing is charged unless the kitty can be created.
+        pub fn mint_token(
+            &mut self,
+            minter: AccountId,
+            id: KittyId,
+            genes: [u8; 32],
+            generation: u16,
+        ) -> Result<()> {
+            if self.exists(id) {
+                return Err(Error::TokenExists);
+            }
+            if minter == AccountId::from([0x0; 32]) || (id == KittyId::from(0) && self.reserve_zero_id) {
+                return Err(Error::NotAllowed);
+            }
+            self.ensure_can_hold(&minter, 1)?;
+
+            let prices = self.charge_mint_price(minter, &[id])?;
+            self.create_token(minter, id, genes, generation)?;
+
+            self.env().emit_event(Minted {
+                to: minter,
+                id,
+                price: prices[0],
+            });
+            Ok(())
+        }
+
+        /// Charges `minter` the mint price of every kitty of `ids`, failing if that many
+        /// more kitties would exceed the maximum supply. Returns the price charged for each
+        /// kitty.
+        pub fn charge_mint_price(
+            &mut self,
+            minter: AccountId,
+            ids: &[KittyId],
+        ) -> Result<Vec<u128>> {
+            let count = u32::try_from(ids.len()).map_err(|_| Error::MaxSupplyReached)?;
+            let prices: Vec<u128> = ids.iter().map(|&id| self.mint_price_of(id)).collect();
+            let amount = prices
+                .iter()
+                .try_fold(0u128, |amount, &price| amount.checked_add(price))
+                .ok_or(Error::PriceOverflow)?;
+            self.charge_for_kitties(minter, count, amount)?;
+            self.burn_ingredient(minter, count)?;
+            Ok(prices)
+        }
+
+        /// Burns the ingredient for `count` new kitties from `minter` by sending it to the
+        /// zero address.
+        pub fn burn_ingredient(&mut self, minter: AccountId, count: u32) -> Result<()> {
+            let Some(ingredient_token) = self.ingredient_token else {
+                return Ok(());
+            };
+            let amount = self
+                .ingredient_amount
+                .checked_mul(count.into())
+                .ok_or(Error::IngredientBurnFail)?;
+            if amount == 0 {
+                return Ok(());
+            }
+
+            let mut ingredient: ink::contract_ref!(TERC20) = ingredient_token.into();
+            let burn_result =
+                ingredient.transfer_from(minter, AccountId::from([0x0; 32]), amount);
+            if burn_result.is_err() {
+                return Err(Error::IngredientBurnFail);
+            }
+
+            Ok(())
+        }
+
+        /// Charges `payer` `amount` for `count` new kitties, failing if that many more
+        /// kitties would exceed the maximum supply.
+        pub fn charge_for_kitties(
+            &mut self,
+            payer: AccountId,
+            count: u32,
+            amount: u128,
+        ) -> Result<()> {
+            self.ensure_not_paused()?;
+            let kitties_account = self.env().account_id();
+
+            if let Some(max_supply) = self.max_supply {
+                if self.minted_total.saturating_add(count) > max_supply {
+                    return Err(Error::MaxSupplyReached);
+                }
+            }
+
+            // Free kitties don't need a payment token
+            if amount > 0 {
+                let payment_result =
+                    self.acceptable_erc20.transfer_from(payer, kitties_account, amount);
+                if payment_result.is_err() {
+                    return Err(Error::CoinTransferFail);
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Creates kitty `id` of `generation` with `genes` for `to` without charging for it.
+        pub fn create_token(
+            &mut self,
+            to: AccountId,
+            id: KittyId,
+            genes: [u8; 32],
+            generation: u16,
+        ) -> Result<()> {
+            self.add_token_to(&to, id)?;
+            self.minted_total += 1;
+            self.total_minted += 1;
+            self.genes.insert(id, &genes);
+            self.generation.insert(id, &generation);
+            if self.roll_lucky(id) {
+                self.lucky.insert(id, &true);
+            } else {
+                self.lucky.remove(id);
+            }
+
+            self.env().emit_event(Transfer {
+                from: Some(AccountId::from([0x0; 32])),
+                to: Some(to),
+                id,
+            });
+            Ok(())
+        }
+
+        /// Approves or disapproves the operator to transfer all kitties of the caller.
+        /// An approval never expires.
+        pub fn approve_for_all(&mut self, to: AccountId, approved: bool) -> Result<()> {
+            self.set_operator(to, approved.then_some(BlockNumber::MAX))
+        }
+
+        /// Approves `operator` to transfer all kitties of the caller up to and including
+        /// block `until_block`.
+        #[ink(message)]
+        pub fn approve_for_all_until(
+            &mut self,
+            operator: AccountId,
+            until_block: BlockNumber,
+        ) -> Result<()> {
+            self.set_operator(operator, Some(until_block))
+        }
+
+        /// Approves operator `to` for all kitties of the caller up to block `until`, or
+        /// disapproves it if `until` is `None`.
+        fn set_operator(&mut self, to: AccountId, until: Option<BlockNumber>) -> Result<()> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            if to == caller {
+                return Err(Error::NotAllowed);
+            }
+            self.env().emit_event(ApprovalForAll {
+                owner: caller,
+                operator: to,
+                approved: until.is_some(),
+            });
+
+            if let Some(until) = until {
+                self.operator_approvals.insert((&caller, &to), &until);
+            } else {
+                self.operator_approvals.remove((&caller, &to));
+            }
+
+            Ok(())
+        }
+
+        /// Approve the passed `AccountId` to transfer the specified kitty on behalf of
+        /// the message's sender.
+        pub fn approve_for(&mut self, to: &AccountId, id: KittyId) -> Result<()> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let owner = self.owner_of(id).ok_or(Error::TokenNotFound)?;
+            if !(owner == caller || self.approved_for_all(owner, caller)) {
+                return Err(Error::NotAllowed);
+            };
+            self.ensure_not_locked(id)?;
+
+            // A new approval replaces the previous one, approving the zero address clears it.
+            if *to == AccountId::from([0x0; 32]) {
+                self.token_approvals.remove(id);
+            } else {
+                self.token_approvals.insert(id, to);
+            }
+
+            self.env().emit_event(Approval {
+                from: caller,
+                to: *to,
+                id,
+            });
+
+            Ok(())
+        }
+
+        /// Removes existing approval from kitty `id`.
+        ///
+        /// Emits an `Approval` event to the zero address if an approval was removed.
+        pub fn clear_approval(&mut self, id: KittyId) {
+            if self.token_approvals.take(id).is_none() {
+                return;
+            }
+
+            if let Some(owner) = self.owner_of(id) {
+                self.env().emit_event(Approval {
+                    from: owner,
+                    to: AccountId::from([0x0; 32]),
+                    id,
+                });
+            }
+        }
+
+        // Returns the total number of kitties from an account.
+        pub fn balance_of_or_zero(&self, of: &AccountId) -> u32 {
+            self.owned_kitties_count.get(of).unwrap_or(0)
+        }
+
+        /// Gets an operator on other Account's behalf.
+        pub fn approved_for_all(&self, owner: AccountId, operator: AccountId) -> bool {
+            self.operator_approvals
+                .get((&owner, &operator))
+                .is_some_and(|until| self.env().block_number() <= until)
+        }
+
+        /// Returns true if the `AccountId` `from` is the owner of kitty `id`
+        /// or it has been approved on behalf of the kitty `id` owner.
+        pub fn approved_or_owner(&self, from: Option<AccountId>, id: KittyId) -> bool {
+            match (self.owner_of(id), from) {
+                (Some(owner), Some(from)) => self.can_operate(owner, from, id),
+                _ => false,
+            }
+        }
+
+        /// Returns `true` if `operator` is the owner of kitty `id`, its approved account or
+        /// an operator approved for all kitties of the owner.
+        ///
+        /// Pausing and market locks are not taken into account.
+        #[ink(message)]
+        pub fn can_transfer(&self, operator: AccountId, id: KittyId) -> bool {
+            self.approved_or_owner(Some(operator), id)
+        }
+
+        /// Returns `true` if `operator` can currently act on kitty `id` of `owner`.
+        ///
+        /// This holds for the owner itself, the approved account of the kitty and any
+        /// operator approved for all kitties of the owner.
+        #[ink(message)]
+        pub fn can_operate(&self, owner: AccountId, operator: AccountId, id: KittyId) -> bool {
+            if operator == AccountId::from([0x0; 32]) || self.owner_of(id) != Some(owner) {
+                return false;
+            }
+
+            operator == owner
+                || self.token_approvals.get(id) == Some(operator)
+                || self.approved_for_all(owner, operator)
+        }
+
+        /// Returns true if kitty `id` exists or false if it does not.
+        pub fn exists(&self, id: KittyId) -> bool {
+            self.kitty_owner.contains(id)
+        }
+    }
+
+    impl TERC721 for Kitties {
+        /// Returns the balance of the owner.
+        ///
+        /// This represents the amount of unique kitties the owner has.
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> u32 {
+            self.balance_of_or_zero(&owner)
+        }
+
+        /// Returns the owner of the kitty.
+        #[ink(message)]
+        fn owner_of(&self, id: KittyId) -> Option<AccountId> {
+            self.kitty_owner.get(id)
+        }
+
+        /// Returns the approved account ID for this kitty if any.
+        #[ink(message)]
+        fn get_approved(&self, id: KittyId) -> Option<AccountId> {
+            self.token_approvals.get(id)
+        }
+
+        /// Returns `true` if the operator is approved by the owner.
+        #[ink(message)]
+        fn is_approved_for_all(&self, owner: AccountId, operator: AccountId) -> bool {
+            self.approved_for_all(owner, operator)
+        }
+
+        /// Approves or disapproves the operator for all kitties of the caller.
+        #[ink(message)]
+        fn set_approval_for_all(&mut self, to: AccountId, approved: bool) -> Result<()> {
+            self.approve_for_all(to, approved)?;
+            Ok(())
+        }
+
+        /// Approves the account to transfer the specified kitty on behalf of the caller.
+        #[ink(message)]
+        fn approve(&mut self, to: AccountId, id: KittyId) -> Result<()> {
+            self.approve_for(&to, id)?;
+            Ok(())
+        }
+
+        /// Transfers the kitty from the caller to the given destination.
+        #[ink(message)]
+        fn transfer(&mut self, destination: AccountId, id: KittyId) -> Result<()> {
+            let caller = self.env().caller();
+            self.transfer_token_from(&caller, &destination, id)?;
+            Ok(())
+        }
+
+        /// Transfer approved or owned kitty.
+        #[ink(message)]
+        fn transfer_from(&mut self, from: AccountId, to: AccountId, id: KittyId) -> Result<()> {
+            self.transfer_token_from(&from, &to, id)?;
+            Ok(())
+        }
+
+        /// Transfer approved or owned kitty, checking that a contract recipient accepts it.
+        #[ink(message)]
+        fn safe_transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            id: KittyId,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            self.transfer_token_from(&from, &to, id)?;
+            self.check_on_erc721_received(&from, &to, id, data)?;
+            Ok(())
+        }
+
+        /// Creates a new kitty.
+        #[ink(message)]
+        fn mint(&mut self, id: KittyId) -> Result<()> {
+            self.mint_token(self.env().caller(), id, self.pseudo_random(id), 0)
+        }
+
+        /// Deletes an existing kitty. The owner, the approved account or an operator of
+        /// the owner can burn the kitty.
+        #[ink(message)]
+        fn burn(&mut self, id: KittyId) -> Result<()> {
+            let caller = self.env().caller();
+            let owner = self.owner_of(id).ok_or(Error::TokenNotFound)?;
+            if !self.approved_or_owner(Some(caller), id) {
+                return Err(Error::NotApproved);
+            };
+            self.ensure_not_locked(id)?;
+
+            self.burn_token(&owner, id)
+        }
+    }
+
+    impl TERC2981 for Kitties {
+        /// Returns the royalty receiver and the royalty amount owed for selling kitty `id`
+        /// at `sale_price`. Every kitty uses the default royalty.
+        #[ink(message)]
+        fn royalty_info(&self, _id: KittyId, sale_price: u128) -> (AccountId, u128) {
+            let amount = sale_price.saturating_mul(self.royalty_basis_points.into()) / 10_000;
+            (self.royalty_receiver, amount)
+        }
+    }
+
+    impl TKittiesMarket for Kitties {
+        /// Locks or unlocks a listed kitty. Only the allowlisted market can call this.
+        #[ink(message)]
+        fn set_locked_for_market(&mut self, id: KittyId, locked: bool) -> Result<()> {
+            if Some(self.env().caller()) != self.market {
+                return Err(Error::NotAllowed);
+            }
+            if !self.exists(id) {
+                return Err(Error::TokenNotFound);
+            }
+
+            if locked {
+                self.locked_for_market.insert(id, &true);
+            } else {
+                self.locked_for_market.remove(id);
+            }
+
+            Ok(())
+        }
+
+        /// Burns a kitty listed on the market. Only the allowlisted market can call this.
+        #[ink(message)]
+        fn burn_for_market(&mut self, id: KittyId) -> Result<()> {
+            if Some(self.env().caller()) != self.market {
+                return Err(Error::NotAllowed);
+            }
+            let owner = self.owner_of(id).ok_or(Error::TokenNotFound)?;
+
+            self.burn_token(&owner, id)
+        }
+
+        /// Creates kitty `id` for `to`, charging `to` the mint price, and approves the
+        /// market for it. Only the allowlisted market can call this.
+        #[ink(message)]
+        fn mint_for_market(&mut self, id: KittyId, to: AccountId) -> Result<()> {
+            let caller = self.env().caller();
+            if Some(caller) != self.market {
+                return Err(Error::NotAllowed);
+            }
+            self.mint_token(to, id, self.pseudo_random(id), 0)?;
+
+            self.token_approvals.insert(id, &caller);
+            self.env().emit_event(Approval {
+                from: to,
+                to: caller,
+                id,
+            });
+
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    impl Kitties {
+        /// Creates a contract with free mints and no royalty for unit tests, where the
+        /// payment token is never called.
+        pub fn new_default() -> Self {
+            Self::new(
+                AccountId::from([0x1; 32]),
+                0,
+                None,
+                AccountId::from([0x0; 32]),
+                0,
+                false,
+            )
+        }
     }
 
-    /// Unit tests
-    #[cfg(test)]
-    mod tests {
-        /// Imports all the definitions from the outer scope so we can use them here.
-        use super::*;
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+
+        type Event = <Kitties as ::ink::reflect::ContractEventBase>::Type;
+
+        fn ids(raw: &[u32]) -> Vec<KittyId> {
+            raw.iter().copied().map(KittyId::from).collect()
+        }
+
+        #[ink::test]
+        fn mint_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Kitty 1 does not exists.
+            assert_eq!(kitties.owner_of(KittyId::from(1)), None);
+            // Alice does not owns kitties.
+            assert_eq!(kitties.balance_of(accounts.alice), 0);
+            // Create kitty Id 1.
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Alice owns 1 kitty.
+            assert_eq!(kitties.balance_of(accounts.alice), 1);
+        }
+
+        #[ink::test]
+        fn mint_auto_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Sequential mints get distinct increasing ids.
+            assert_eq!(kitties.mint_auto(), Ok(KittyId::from(1)));
+            assert_eq!(kitties.mint_auto(), Ok(KittyId::from(2)));
+            // Ids minted explicitly are skipped.
+            assert_eq!(kitties.mint(KittyId::from(3)), Ok(()));
+            assert_eq!(kitties.mint_auto(), Ok(KittyId::from(4)));
+            // Alice owns all 4 kitties.
+            assert_eq!(kitties.balance_of(accounts.alice), 4);
+            assert_eq!(kitties.owner_of(KittyId::from(4)), Some(accounts.alice));
+        }
+
+        #[ink::test]
+        fn mint_batch_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint_batch(ids(&[1, 2, 3])), Ok(()));
+            // Alice owns all 3 kitties.
+            assert_eq!(kitties.balance_of(accounts.alice), 3);
+            assert_eq!(kitties.owner_of(KittyId::from(3)), Some(accounts.alice));
+            assert_eq!(kitties.mint_progress(), (3, None));
+            // One Transfer and one Minted event per kitty.
+            let emitted_events = ink::env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 6);
+            let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[1].data[..])
+                .expect("decoded error");
+            match decoded {
+                Event::Minted(Minted { to, id, price }) => {
+                    assert_eq!(to, accounts.alice, "minted to error");
+                    assert_eq!(id, KittyId::from(1), "minted id error");
+                    assert_eq!(price, 0, "minted price error");
+                }
+                _ => panic!("Minted event not emitted"),
+            }
+        }
+
+        #[ink::test]
+        fn mint_batch_with_existing_id_mints_nothing() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint(KittyId::from(2)), Ok(()));
+            // Kitty 2 is taken, so neither 1 nor 3 gets minted.
+            assert_eq!(kitties.mint_batch(ids(&[1, 2, 3])), Err(Error::TokenExists));
+            assert_eq!(kitties.owner_of(KittyId::from(1)), None);
+            assert_eq!(kitties.owner_of(KittyId::from(3)), None);
+            // Ids repeated in the batch fail the same way.
+            assert_eq!(kitties.mint_batch(ids(&[4, 4])), Err(Error::TokenExists));
+            assert_eq!(kitties.owner_of(KittyId::from(4)), None);
+            assert_eq!(kitties.balance_of(accounts.alice), 1);
+        }
+
+        #[ink::test]
+        fn mint_existing_should_fail() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1.
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // The first Transfer and Minted events take place
+            assert_eq!(2, ink::env::test::recorded_events().count());
+            // Alice owns 1 kitty.
+            assert_eq!(kitties.balance_of(accounts.alice), 1);
+            // Alice owns kitty Id 1.
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.alice));
+            // Cannot create  kitty Id if it exists.
+            // Bob cannot own kitty Id 1.
+            assert_eq!(kitties.mint(KittyId::from(1)), Err(Error::TokenExists));
+        }
+
+        #[ink::test]
+        fn can_transfer_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // The owner can transfer it.
+            assert!(kitties.can_transfer(accounts.alice, KittyId::from(1)));
+            // Bob is approved for kitty 1.
+            assert!(!kitties.can_transfer(accounts.bob, KittyId::from(1)));
+            assert_eq!(kitties.approve(accounts.bob, KittyId::from(1)), Ok(()));
+            assert!(kitties.can_transfer(accounts.bob, KittyId::from(1)));
+            // Charlie is an operator of Alice.
+            assert!(!kitties.can_transfer(accounts.charlie, KittyId::from(1)));
+            assert_eq!(kitties.set_approval_for_all(accounts.charlie, true), Ok(()));
+            assert!(kitties.can_transfer(accounts.charlie, KittyId::from(1)));
+            // Django is neither.
+            assert!(!kitties.can_transfer(accounts.django, KittyId::from(1)));
+            // Nobody can transfer a kitty that doesn't exist.
+            assert!(!kitties.can_transfer(accounts.alice, KittyId::from(2)));
+        }
+
+        #[ink::test]
+        fn free_mints_are_unlimited() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance with free mints.
+            let kitties = Kitties::new_default();
+            assert_eq!(kitties.remaining_mints(accounts.alice), u32::MAX);
+        }
+
+        #[ink::test]
+        fn mint_existing_charges_nothing() {
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // With a price, charging would call the payment token, which doesn't exist
+            // in unit tests.
+            kitties.mint_price = 10;
+            assert_eq!(kitties.mint(KittyId::from(1)), Err(Error::TokenExists));
+            assert_eq!(kitties.total_supply(), 1);
+        }
+
+        #[ink::test]
+        fn transfer_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Alice owns kitty 1
+            assert_eq!(kitties.balance_of(accounts.alice), 1);
+            // Bob does not owns any kitty
+            assert_eq!(kitties.balance_of(accounts.bob), 0);
+            // The first Transfer and Minted events take place
+            assert_eq!(2, ink::env::test::recorded_events().count());
+            // Alice transfers kitty 1 to Bob
+            assert_eq!(kitties.transfer(accounts.bob, KittyId::from(1)), Ok(()));
+            // The second Transfer event takes place
+            assert_eq!(3, ink::env::test::recorded_events().count());
+            // Bob owns kitty 1
+            assert_eq!(kitties.balance_of(accounts.bob), 1);
+        }
+
+        #[ink::test]
+        fn self_transfer_keeps_balance() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice and let Bob manage it.
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.approve(accounts.bob, KittyId::from(1)), Ok(()));
+            let events = ink::env::test::recorded_events().count();
+            // Alice transfers kitty 1 to herself.
+            assert_eq!(kitties.transfer(accounts.alice, KittyId::from(1)), Ok(()));
+            // The approval is cleared and a single Transfer event takes place.
+            assert_eq!(events + 2, ink::env::test::recorded_events().count());
+            assert_eq!(kitties.balance_of(accounts.alice), 1);
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.alice));
+            assert_eq!(kitties.get_approved(KittyId::from(1)), None);
+            // Bob still can't transfer kitty 1 to Alice.
+            set_caller(accounts.bob);
+            assert_eq!(
+                kitties.transfer_from(accounts.alice, accounts.alice, KittyId::from(1)),
+                Err(Error::NotApproved)
+            );
+        }
+
+        #[ink::test]
+        fn invalid_transfer_should_fail() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Transfer kitty fails if it does not exists.
+            assert_eq!(
+                kitties.transfer(accounts.bob, KittyId::from(2)),
+                Err(Error::TokenNotFound)
+            );
+            // Kitty Id 2 does not exists.
+            assert_eq!(kitties.owner_of(KittyId::from(2)), None);
+            // Create kitty Id 2.
+            assert_eq!(kitties.mint(KittyId::from(2)), Ok(()));
+            // Alice owns 1 kitty.
+            assert_eq!(kitties.balance_of(accounts.alice), 1);
+            // Kitty Id 2 is owned by Alice.
+            assert_eq!(kitties.owner_of(KittyId::from(2)), Some(accounts.alice));
+            // Set Bob as caller
+            set_caller(accounts.bob);
+            // Bob cannot transfer not owned kitties.
+            assert_eq!(
+                kitties.transfer(accounts.eve, KittyId::from(2)),
+                Err(Error::NotApproved)
+            );
+        }
+
+        #[ink::test]
+        fn safe_transfer_to_account_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Bob is a plain account, so no receiver check takes place.
+            assert_eq!(
+                kitties.safe_transfer_from(accounts.alice, accounts.bob, KittyId::from(1), Vec::new()),
+                Ok(())
+            );
+            // Bob owns kitty 1
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.bob));
+            assert_eq!(kitties.balance_of(accounts.alice), 0);
+            assert_eq!(kitties.balance_of(accounts.bob), 1);
+        }
+
+        #[ink::test]
+        fn transfer_batch_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint_batch(ids(&[1, 2, 3])), Ok(()));
+            // Alice moves all her kitties to Bob at once.
+            assert_eq!(kitties.transfer_batch(accounts.bob, ids(&[1, 2, 3])), Ok(()));
+            assert_eq!(kitties.balance_of(accounts.alice), 0);
+            assert_eq!(kitties.balance_of(accounts.bob), 3);
+            assert_eq!(kitties.owner_of(KittyId::from(2)), Some(accounts.bob));
+            // One Transfer event per kitty after the 6 mint events.
+            assert_eq!(ink::env::test::recorded_events().count(), 9);
+        }
+
+        #[ink::test]
+        fn transfer_batch_with_unowned_kitty_transfers_nothing() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint_batch(ids(&[1, 2])), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(kitties.mint(KittyId::from(3)), Ok(()));
+            // Alice doesn't own kitty 3, so kitties 1 and 2 stay with her.
+            set_caller(accounts.alice);
+            assert_eq!(
+                kitties.transfer_batch(accounts.charlie, ids(&[1, 2, 3])),
+                Err(Error::NotApproved)
+            );
+            assert_eq!(
+                kitties.transfer_batch(accounts.charlie, ids(&[1, 4])),
+                Err(Error::TokenNotFound)
+            );
+            assert_eq!(kitties.balance_of(accounts.alice), 2);
+            assert_eq!(kitties.balance_of(accounts.charlie), 0);
+        }
+
+        #[ink::test]
+        fn transfer_distribution_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint_batch(ids(&[1, 2, 3])), Ok(()));
+            assert_eq!(
+                kitties.transfer_distribution(vec![
+                    (KittyId::from(1), accounts.bob),
+                    (KittyId::from(2), accounts.charlie),
+                    (KittyId::from(3), accounts.django),
+                ]),
+                Ok(())
+            );
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.bob));
+            assert_eq!(kitties.owner_of(KittyId::from(2)), Some(accounts.charlie));
+            assert_eq!(kitties.owner_of(KittyId::from(3)), Some(accounts.django));
+            assert_eq!(kitties.balance_of(accounts.alice), 0);
+            // One Transfer event per kitty after the 6 mint events.
+            assert_eq!(ink::env::test::recorded_events().count(), 9);
+        }
+
+        #[ink::test]
+        fn transfer_distribution_with_invalid_entry_transfers_nothing() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint_batch(ids(&[1, 2])), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(kitties.mint(KittyId::from(3)), Ok(()));
+            // Alice can't distribute Bob's kitty 3.
+            set_caller(accounts.alice);
+            assert_eq!(
+                kitties.transfer_distribution(vec![
+                    (KittyId::from(1), accounts.charlie),
+                    (KittyId::from(2), accounts.django),
+                    (KittyId::from(3), accounts.eve),
+                ]),
+                Err(Error::NotApproved)
+            );
+            // Nor send a kitty to the zero address.
+            assert_eq!(
+                kitties.transfer_distribution(vec![
+                    (KittyId::from(1), accounts.charlie),
+                    (KittyId::from(2), AccountId::from([0x0; 32])),
+                ]),
+                Err(Error::NotAllowed)
+            );
+            assert_eq!(kitties.balance_of(accounts.alice), 2);
+            assert_eq!(kitties.balance_of(accounts.charlie), 0);
+        }
+
+        #[ink::test]
+        fn transfer_from_wrong_owner_should_fail() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice and kitty Id 2 for Bob.
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(kitties.mint(KittyId::from(2)), Ok(()));
+            // Alice owns kitty 1 but claims it comes from Bob.
+            set_caller(accounts.alice);
+            assert_eq!(
+                kitties.transfer_from(accounts.bob, accounts.eve, KittyId::from(1)),
+                Err(Error::NotOwner)
+            );
+            // No balance was corrupted.
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.alice));
+            assert_eq!(kitties.balance_of(accounts.alice), 1);
+            assert_eq!(kitties.balance_of(accounts.bob), 1);
+            assert_eq!(kitties.balance_of(accounts.eve), 0);
+        }
+
+        #[ink::test]
+        fn approved_transfer_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1.
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Kitty Id 1 is owned by Alice.
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.alice));
+            // Approve kitty Id 1 transfer for Bob on behalf of Alice.
+            assert_eq!(kitties.approve(accounts.bob, KittyId::from(1)), Ok(()));
+            // Set Bob as caller
+            set_caller(accounts.bob);
+            // Bob transfers kitty Id 1 from Alice to Eve.
+            assert_eq!(
+                kitties.transfer_from(accounts.alice, accounts.eve, KittyId::from(1)),
+                Ok(())
+            );
+            // KittyId 3 is owned by Eve.
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.eve));
+            // Alice does not owns kitties.
+            assert_eq!(kitties.balance_of(accounts.alice), 0);
+            // Bob does not owns kitties.
+            assert_eq!(kitties.balance_of(accounts.bob), 0);
+            // Eve owns 1 kitty.
+            assert_eq!(kitties.balance_of(accounts.eve), 1);
+        }
+
+        #[ink::test]
+        fn approve_replaces_previous_approval() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1.
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Approve kitty Id 1 transfer for Bob on behalf of Alice.
+            assert_eq!(kitties.approve(accounts.bob, KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.get_approved(KittyId::from(1)), Some(accounts.bob));
+            // Alice changes her mind and approves Charlie instead.
+            assert_eq!(kitties.approve(accounts.charlie, KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.get_approved(KittyId::from(1)), Some(accounts.charlie));
+            // Approving the zero address clears the approval.
+            assert_eq!(kitties.approve(AccountId::from([0x0; 32]), KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.get_approved(KittyId::from(1)), None);
+            // Two mint events plus three Approval events.
+            assert_eq!(5, ink::env::test::recorded_events().count());
+        }
+
+        #[ink::test]
+        fn approve_nonexistent_kitty_fails() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Kitty 1 does not exist yet.
+            assert_eq!(
+                kitties.approve(accounts.bob, KittyId::from(1)),
+                Err(Error::TokenNotFound)
+            );
+            // Nor once it has been burned.
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.burn(KittyId::from(1)), Ok(()));
+            assert_eq!(
+                kitties.approve(accounts.bob, KittyId::from(1)),
+                Err(Error::TokenNotFound)
+            );
+            assert_eq!(kitties.get_approved(KittyId::from(1)), None);
+        }
+
+        #[ink::test]
+        fn transfer_clears_approval_with_event() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1.
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Approve kitty Id 1 transfer for Bob on behalf of Alice.
+            assert_eq!(kitties.approve(accounts.bob, KittyId::from(1)), Ok(()));
+            // Alice transfers kitty 1 to Eve.
+            assert_eq!(kitties.transfer(accounts.eve, KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.get_approved(KittyId::from(1)), None);
+            // Two mint events, approval, approval clearing and transfer events.
+            let emitted_events = ink::env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(5, emitted_events.len());
+            let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[3].data[..])
+                .expect("decoded error");
+            match decoded {
+                Event::Approval(Approval { from, to, id }) => {
+                    assert_eq!(from, accounts.alice, "approval from error");
+                    assert_eq!(to, AccountId::from([0x0; 32]), "approval to error");
+                    assert_eq!(id, KittyId::from(1), "approval id error");
+                }
+                _ => panic!("Approval event not emitted"),
+            }
+            // Without an approval no clearing event is emitted.
+            set_caller(accounts.eve);
+            assert_eq!(kitties.transfer(accounts.alice, KittyId::from(1)), Ok(()));
+            assert_eq!(6, ink::env::test::recorded_events().count());
+        }
+
+        #[ink::test]
+        fn operator_approval_expires() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Bob operates Alice's kitties for this and the next block.
+            let now = ink::env::block_number::<ink::env::DefaultEnvironment>();
+            assert_eq!(kitties.approve_for_all_until(accounts.bob, now + 1), Ok(()));
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert!(kitties.is_approved_for_all(accounts.alice, accounts.bob));
+            assert!(kitties.can_transfer(accounts.bob, KittyId::from(1)));
+            // After the expiry block Bob loses his rights.
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert!(!kitties.is_approved_for_all(accounts.alice, accounts.bob));
+            set_caller(accounts.bob);
+            assert_eq!(
+                kitties.transfer_from(accounts.alice, accounts.bob, KittyId::from(1)),
+                Err(Error::NotApproved)
+            );
+            // A plain approval does not expire.
+            set_caller(accounts.alice);
+            assert_eq!(kitties.set_approval_for_all(accounts.bob, true), Ok(()));
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert!(kitties.is_approved_for_all(accounts.alice, accounts.bob));
+        }
+
+        #[ink::test]
+        fn approved_for_all_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1.
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Create kitty Id 2.
+            assert_eq!(kitties.mint(KittyId::from(2)), Ok(()));
+            // Alice owns 2 kitties.
+            assert_eq!(kitties.balance_of(accounts.alice), 2);
+            // Approve kitty Id 1 transfer for Bob on behalf of Alice.
+            assert_eq!(
+                kitties.set_approval_for_all(accounts.bob, true),
+                Ok(())
+            );
+            // Bob is an approved operator for Alice
+            assert!(kitties.is_approved_for_all(accounts.alice, accounts.bob));
+            // Set Bob as caller
+            set_caller(accounts.bob);
+            // Bob transfers kitty Id 1 from Alice to Eve.
+            assert_eq!(
+                kitties.transfer_from(accounts.alice, accounts.eve, KittyId::from(1)),
+                Ok(())
+            );
+            // KittyId 1 is owned by Eve.
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.eve));
+            // Alice owns 1 kitty.
+            assert_eq!(kitties.balance_of(accounts.alice), 1);
+            // Bob transfers kitty Id 2 from Alice to Eve.
+            assert_eq!(
+                kitties.transfer_from(accounts.alice, accounts.eve, KittyId::from(2)),
+                Ok(())
+            );
+            // Bob does not own kitties.
+            assert_eq!(kitties.balance_of(accounts.bob), 0);
+            // Eve owns 2 kitties.
+            assert_eq!(kitties.balance_of(accounts.eve), 2);
+            // Remove operator approval for Bob on behalf of Alice.
+            set_caller(accounts.alice);
+            assert_eq!(
+                kitties.set_approval_for_all(accounts.bob, false),
+                Ok(())
+            );
+            // Bob is not an approved operator for Alice.
+            assert!(!kitties.is_approved_for_all(accounts.alice, accounts.bob));
+        }
+
+        #[ink::test]
+        fn not_approved_transfer_should_fail() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1.
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Alice owns 1 kitty.
+            assert_eq!(kitties.balance_of(accounts.alice), 1);
+            // Bob does not owns kitties.
+            assert_eq!(kitties.balance_of(accounts.bob), 0);
+            // Eve does not owns kitties.
+            assert_eq!(kitties.balance_of(accounts.eve), 0);
+            // Set Eve as caller
+            set_caller(accounts.eve);
+            // Eve is not an approved operator by Alice.
+            assert_eq!(
+                kitties.transfer_from(accounts.alice, accounts.frank, KittyId::from(1)),
+                Err(Error::NotApproved)
+            );
+            // Alice owns 1 kitty.
+            assert_eq!(kitties.balance_of(accounts.alice), 1);
+            // Bob does not owns kitties.
+            assert_eq!(kitties.balance_of(accounts.bob), 0);
+            // Eve does not owns kitties.
+            assert_eq!(kitties.balance_of(accounts.eve), 0);
+        }
+
+        #[ink::test]
+        fn burn_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Alice owns 1 kitty.
+            assert_eq!(kitties.balance_of(accounts.alice), 1);
+            // Alice owns kitty Id 1.
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.alice));
+            // Destroy kitty Id 1.
+            assert_eq!(kitties.burn(KittyId::from(1)), Ok(()));
+            // Alice does not owns kitties.
+            assert_eq!(kitties.balance_of(accounts.alice), 0);
+            // Kitty Id 1 does not exists
+            assert_eq!(kitties.owner_of(KittyId::from(1)), None);
+        }
+
+        #[ink::test]
+        fn total_minted_counts_burned_kitties() {
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint_batch(ids(&[1, 2, 3])), Ok(()));
+            assert_eq!(kitties.burn(KittyId::from(2)), Ok(()));
+            assert_eq!(kitties.total_minted(), 3);
+            assert_eq!(kitties.total_burned(), 1);
+            assert_eq!(kitties.total_supply(), 2);
+        }
+
+        #[ink::test]
+        fn burn_fails_token_not_found() {
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Try burning a non existent kitty
+            assert_eq!(kitties.burn(KittyId::from(1)), Err(Error::TokenNotFound));
+        }
+
+        #[ink::test]
+        fn burn_fails_not_approved() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Try burning this kitty with an unrelated account
+            set_caller(accounts.eve);
+            assert_eq!(kitties.burn(KittyId::from(1)), Err(Error::NotApproved));
+            // Alice still owns kitty Id 1.
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.alice));
+        }
+
+        #[ink::test]
+        fn burn_by_approved_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Approve kitty Id 1 for Bob on behalf of Alice.
+            assert_eq!(kitties.approve(accounts.bob, KittyId::from(1)), Ok(()));
+            // Bob burns kitty Id 1.
+            set_caller(accounts.bob);
+            assert_eq!(kitties.burn(KittyId::from(1)), Ok(()));
+            // Kitty Id 1 does not exists and its approval is gone.
+            assert_eq!(kitties.owner_of(KittyId::from(1)), None);
+            assert_eq!(kitties.get_approved(KittyId::from(1)), None);
+            assert_eq!(kitties.balance_of(accounts.alice), 0);
+        }
+
+        #[ink::test]
+        fn burn_by_operator_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Bob is an approved operator for Alice.
+            assert_eq!(kitties.set_approval_for_all(accounts.bob, true), Ok(()));
+            // Bob burns kitty Id 1.
+            set_caller(accounts.bob);
+            assert_eq!(kitties.burn(KittyId::from(1)), Ok(()));
+            // Kitty Id 1 does not exists.
+            assert_eq!(kitties.owner_of(KittyId::from(1)), None);
+            assert_eq!(kitties.balance_of(accounts.alice), 0);
+        }
+
+        #[ink::test]
+        fn pause_blocks_state_changes_until_unpaused() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.set_paused(true), Ok(()));
+
+            assert_eq!(kitties.mint(KittyId::from(2)), Err(Error::ContractPaused));
+            assert_eq!(kitties.transfer(accounts.bob, KittyId::from(1)), Err(Error::ContractPaused));
+            assert_eq!(
+                kitties.transfer_from(accounts.alice, accounts.bob, KittyId::from(1)),
+                Err(Error::ContractPaused)
+            );
+            assert_eq!(kitties.approve(accounts.bob, KittyId::from(1)), Err(Error::ContractPaused));
+            // Reads keep working.
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.alice));
+            assert_eq!(kitties.balance_of(accounts.alice), 1);
+
+            assert_eq!(kitties.set_paused(false), Ok(()));
+            assert_eq!(kitties.mint(KittyId::from(2)), Ok(()));
+            assert_eq!(kitties.approve(accounts.bob, KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.transfer(accounts.bob, KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.bob));
+        }
+
+        #[ink::test]
+        fn pause_blocks_operator_approvals() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.set_approval_for_all(accounts.bob, true), Ok(()));
+            assert_eq!(kitties.set_paused(true), Ok(()));
+
+            // Operators can neither be approved nor revoked while paused.
+            assert_eq!(
+                kitties.set_approval_for_all(accounts.charlie, true),
+                Err(Error::ContractPaused)
+            );
+            assert_eq!(
+                kitties.approve_for_all_until(accounts.charlie, 10),
+                Err(Error::ContractPaused)
+            );
+            assert_eq!(
+                kitties.set_approval_for_all(accounts.bob, false),
+                Err(Error::ContractPaused)
+            );
+            assert!(!kitties.is_approved_for_all(accounts.alice, accounts.charlie));
+            assert!(kitties.is_approved_for_all(accounts.alice, accounts.bob));
+
+            assert_eq!(kitties.set_paused(false), Ok(()));
+            assert_eq!(kitties.set_approval_for_all(accounts.charlie, true), Ok(()));
+            assert!(kitties.is_approved_for_all(accounts.alice, accounts.charlie));
+        }
+
+        #[ink::test]
+        fn transfer_ownership_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.owner(), accounts.alice);
+            assert_eq!(kitties.transfer_ownership(accounts.bob), Ok(()));
+            assert_eq!(kitties.owner(), accounts.bob);
+
+            let emitted_events = ink::env::test::recorded_events().collect::<Vec<_>>();
+            let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[0].data[..])
+                .expect("decoded error");
+            match decoded {
+                Event::OwnershipTransferred(OwnershipTransferred { previous, new }) => {
+                    assert_eq!((previous, new), (accounts.alice, accounts.bob));
+                }
+                _ => panic!("OwnershipTransferred event not emitted"),
+            }
+
+            // Only the new owner can configure the contract.
+            assert_eq!(kitties.set_paused(true), Err(Error::NotContractOwner));
+            set_caller(accounts.bob);
+            assert_eq!(kitties.set_paused(true), Ok(()));
+        }
+
+        #[ink::test]
+        fn transfer_ownership_rejects_invalid_handoff() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(
+                kitties.transfer_ownership(AccountId::from([0x0; 32])),
+                Err(Error::NotAllowed)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                kitties.transfer_ownership(accounts.bob),
+                Err(Error::NotContractOwner)
+            );
+            assert_eq!(kitties.owner(), accounts.alice);
+        }
+
+        #[ink::test]
+        fn set_paused_requires_contract_owner() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            set_caller(accounts.bob);
+            assert_eq!(kitties.set_paused(true), Err(Error::NotContractOwner));
+            assert!(!kitties.paused());
+        }
+
+        #[ink::test]
+        fn frozen_kitty_cannot_move_until_unfrozen() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice and approve Bob for it.
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.approve(accounts.bob, KittyId::from(1)), Ok(()));
+            // Only existing kitties can be frozen.
+            assert_eq!(kitties.freeze_token(KittyId::from(2)), Err(Error::TokenNotFound));
+            assert_eq!(kitties.freeze_token(KittyId::from(1)), Ok(()));
+            assert!(kitties.is_frozen(KittyId::from(1)));
+            // Neither the owner nor the approved account can move or burn it.
+            assert_eq!(
+                kitties.transfer(accounts.charlie, KittyId::from(1)),
+                Err(Error::TokenFrozen)
+            );
+            assert_eq!(kitties.burn(KittyId::from(1)), Err(Error::TokenFrozen));
+            set_caller(accounts.bob);
+            assert_eq!(
+                kitties.transfer_from(accounts.alice, accounts.bob, KittyId::from(1)),
+                Err(Error::TokenFrozen)
+            );
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.alice));
+            // Once unfrozen, the kitty can be transferred again.
+            set_caller(accounts.alice);
+            assert_eq!(kitties.unfreeze_token(KittyId::from(1)), Ok(()));
+            assert!(!kitties.is_frozen(KittyId::from(1)));
+            assert_eq!(kitties.transfer(accounts.charlie, KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.charlie));
+        }
+
+        #[ink::test]
+        fn frozen_kitty_cannot_migrate_out() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint_batch(ids(&[1, 2])), Ok(()));
+            assert_eq!(kitties.freeze_token(KittyId::from(2)), Ok(()));
+            assert_eq!(kitties.set_migration_open(true), Ok(()));
+            // The whole batch fails because of kitty 2.
+            assert_eq!(kitties.migrate_out(ids(&[1, 2])), Err(Error::TokenFrozen));
+            assert_eq!(kitties.owner_of(KittyId::from(2)), Some(accounts.alice));
+            // A removed kitty doesn't stay frozen, so a new kitty with its id isn't either.
+            assert_eq!(kitties.remove_token(&accounts.alice, KittyId::from(2)), Ok(()));
+            assert!(!kitties.is_frozen(KittyId::from(2)));
+            assert_eq!(kitties.mint(KittyId::from(2)), Ok(()));
+            assert!(!kitties.is_frozen(KittyId::from(2)));
+        }
+
+        #[ink::test]
+        fn freezing_requires_contract_owner() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(kitties.freeze_token(KittyId::from(1)), Err(Error::NotContractOwner));
+            assert_eq!(kitties.unfreeze_token(KittyId::from(1)), Err(Error::NotContractOwner));
+            assert!(!kitties.is_frozen(KittyId::from(1)));
+        }
+
+        #[ink::test]
+        fn market_lock_blocks_approval_until_unlocked() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Charlie acts as the allowlisted market.
+            assert_eq!(kitties.set_market(Some(accounts.charlie)), Ok(()));
+            // The market locks kitty 1 when it gets listed.
+            set_caller(accounts.charlie);
+            assert_eq!(kitties.set_locked_for_market(KittyId::from(1), true), Ok(()));
+            assert!(kitties.is_locked_for_market(KittyId::from(1)));
+            // Alice cannot change the approval or transfer the listed kitty.
+            set_caller(accounts.alice);
+            assert_eq!(kitties.approve(accounts.bob, KittyId::from(1)), Err(Error::LockedForMarket));
+            assert_eq!(kitties.transfer(accounts.bob, KittyId::from(1)), Err(Error::LockedForMarket));
+            // The market unlocks kitty 1 when it gets unlisted.
+            set_caller(accounts.charlie);
+            assert_eq!(kitties.set_locked_for_market(KittyId::from(1), false), Ok(()));
+            // Alice can approve Bob again.
+            set_caller(accounts.alice);
+            assert_eq!(kitties.approve(accounts.bob, KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.get_approved(KittyId::from(1)), Some(accounts.bob));
+        }
+
+        #[ink::test]
+        fn burn_for_market_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Only the allowlisted market can burn it.
+            assert_eq!(kitties.burn_for_market(KittyId::from(1)), Err(Error::NotAllowed));
+            assert_eq!(kitties.set_market(Some(accounts.charlie)), Ok(()));
+            set_caller(accounts.charlie);
+            assert_eq!(kitties.burn_for_market(KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.owner_of(KittyId::from(1)), None);
+            assert_eq!(kitties.balance_of(accounts.alice), 0);
+            assert_eq!(kitties.burn_for_market(KittyId::from(1)), Err(Error::TokenNotFound));
+        }
+
+        #[ink::test]
+        fn mint_for_market_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Only the allowlisted market can mint for others.
+            assert_eq!(
+                kitties.mint_for_market(KittyId::from(1), accounts.bob),
+                Err(Error::NotAllowed)
+            );
+            assert_eq!(kitties.set_market(Some(accounts.charlie)), Ok(()));
+            set_caller(accounts.charlie);
+            assert_eq!(kitties.mint_for_market(KittyId::from(1), accounts.bob), Ok(()));
+            // Bob owns the kitty and the market is approved for it.
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.bob));
+            assert_eq!(kitties.balance_of(accounts.charlie), 0);
+            assert_eq!(kitties.get_approved(KittyId::from(1)), Some(accounts.charlie));
+            assert_eq!(
+                kitties.mint_for_market(KittyId::from(1), accounts.bob),
+                Err(Error::TokenExists)
+            );
+        }
+
+        #[ink::test]
+        fn market_lock_requires_market() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Without an allowlisted market nobody can lock kitties.
+            assert_eq!(
+                kitties.set_locked_for_market(KittyId::from(1), true),
+                Err(Error::NotAllowed)
+            );
+            // Only the contract owner can allowlist a market.
+            set_caller(accounts.bob);
+            assert_eq!(
+                kitties.set_market(Some(accounts.bob)),
+                Err(Error::NotContractOwner)
+            );
+            assert_eq!(kitties.market(), None);
+        }
+
+        #[ink::test]
+        fn migrate_out_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1, 2 and 3 for Alice
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.mint(KittyId::from(2)), Ok(()));
+            assert_eq!(kitties.mint(KittyId::from(3)), Ok(()));
+            assert_eq!(6, ink::env::test::recorded_events().count());
+            kitties.lucky.insert(KittyId::from(1), &true);
+            kitties.recipient_allowlist.insert(KittyId::from(1), &vec![accounts.bob]);
+            // Migration is closed by default.
+            assert_eq!(kitties.migrate_out(ids(&[1, 2])), Err(Error::MigrationClosed));
+            // Alice opens the migration and migrates out kitty 1 and 2.
+            assert_eq!(kitties.set_migration_open(true), Ok(()));
+            assert_eq!(kitties.migrate_out(ids(&[1, 2])), Ok(()));
+            // Only a single MigrationBatch event was emitted.
+            assert_eq!(7, ink::env::test::recorded_events().count());
+            // Kitty 1 and 2 are gone, Alice still owns kitty 3.
+            assert_eq!(kitties.owner_of(KittyId::from(1)), None);
+            assert_eq!(kitties.owner_of(KittyId::from(2)), None);
+            assert_eq!(kitties.owner_of(KittyId::from(3)), Some(accounts.alice));
+            assert_eq!(kitties.balance_of(accounts.alice), 1);
+            // Nothing about kitty 1 is left behind.
+            assert!(!kitties.is_lucky(KittyId::from(1)));
+            assert_eq!(kitties.recipient_allowlist(KittyId::from(1)), vec![]);
+            // Once the migration is closed kitty 3 cannot be migrated out.
+            assert_eq!(kitties.set_migration_open(false), Ok(()));
+            assert_eq!(kitties.migrate_out(ids(&[3])), Err(Error::MigrationClosed));
+            assert_eq!(kitties.owner_of(KittyId::from(3)), Some(accounts.alice));
+        }
+
+        #[ink::test]
+        fn migrate_out_requires_contract_owner() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.set_migration_open(true), Ok(()));
+            // Bob is not the contract owner.
+            set_caller(accounts.bob);
+            assert_eq!(kitties.set_migration_open(false), Err(Error::NotContractOwner));
+            assert_eq!(kitties.migrate_out(ids(&[1])), Err(Error::NotContractOwner));
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.alice));
+        }
+
+        #[ink::test]
+        fn lucky_mint_works() {
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Every mint is lucky with a 100% chance.
+            assert_eq!(kitties.set_lucky_bps(10_000), Ok(()));
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            assert!(kitties.is_lucky(KittyId::from(1)));
+            // No mint is lucky once the chance is disabled.
+            assert_eq!(kitties.set_lucky_bps(0), Ok(()));
+            assert_eq!(kitties.mint(KittyId::from(2)), Ok(()));
+            assert!(!kitties.is_lucky(KittyId::from(2)));
+        }
+
+        #[ink::test]
+        fn breed_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.mint(KittyId::from(2)), Ok(()));
+            let genes1 = kitties.genes_of(KittyId::from(1)).unwrap();
+            let genes2 = kitties.genes_of(KittyId::from(2)).unwrap();
+            assert_ne!(genes1, genes2);
+
+            // Alice breeds her two kitties into kitty 3.
+            assert_eq!(kitties.breed(KittyId::from(1), KittyId::from(2)), Ok(KittyId::from(3)));
+            assert_eq!(kitties.owner_of(KittyId::from(3)), Some(accounts.alice));
+            let child_genes = kitties.genes_of(KittyId::from(3)).unwrap();
+            assert_ne!(child_genes, genes1);
+            assert_ne!(child_genes, genes2);
+            // Breeding is deterministic.
+            assert_eq!(child_genes, Kitties::mix_genes(&genes1, &genes2));
+
+            let emitted_events = ink::env::test::recorded_events().collect::<Vec<_>>();
+            let decoded = <Event as scale::Decode>::decode(
+                &mut &emitted_events.last().unwrap().data[..],
+            )
+            .expect("decoded error");
+            match decoded {
+                Event::Birth(Birth {
+                    child,
+                    parent1,
+                    parent2,
+                }) => {
+                    assert_eq!((child, parent1, parent2), (KittyId::from(3), KittyId::from(1), KittyId::from(2)));
+                }
+                _ => panic!("Birth event not emitted"),
+            }
+        }
+
+        #[ink::test]
+        fn breed_requires_both_parents() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.breed(KittyId::from(1), KittyId::from(2)), Err(Error::TokenNotFound));
+            assert_eq!(kitties.breed(KittyId::from(1), KittyId::from(1)), Err(Error::NotAllowed));
+            // Bob can't breed Alice's kitty with his own.
+            set_caller(accounts.bob);
+            assert_eq!(kitties.mint(KittyId::from(2)), Ok(()));
+            assert_eq!(kitties.breed(KittyId::from(1), KittyId::from(2)), Err(Error::NotApproved));
+            assert_eq!(kitties.owner_of(KittyId::from(3)), None);
+        }
+
+        #[ink::test]
+        fn breeding_cooldown_works() {
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.mint(KittyId::from(2)), Ok(()));
+            assert!(kitties.ready_to_breed(KittyId::from(1)));
+            assert_eq!(kitties.breed(KittyId::from(1), KittyId::from(2)), Ok(KittyId::from(3)));
+
+            // Both parents cool down, the child can breed right away.
+            assert!(!kitties.ready_to_breed(KittyId::from(1)));
+            assert!(!kitties.ready_to_breed(KittyId::from(2)));
+            assert!(kitties.ready_to_breed(KittyId::from(3)));
+            assert_eq!(kitties.breed(KittyId::from(1), KittyId::from(2)), Err(Error::KittyOnCooldown));
+            assert_eq!(kitties.breed(KittyId::from(3), KittyId::from(2)), Err(Error::KittyOnCooldown));
+
+            // The parents can breed again once the cooldown has passed.
+            for _ in 0..BREEDING_COOLDOWNS[0] - 1 {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            }
+            assert!(!kitties.ready_to_breed(KittyId::from(1)));
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert!(kitties.ready_to_breed(KittyId::from(1)));
+            assert!(kitties.ready_to_breed(KittyId::from(2)));
+            assert_eq!(kitties.breed(KittyId::from(1), KittyId::from(2)), Ok(KittyId::from(4)));
+        }
+
+        #[ink::test]
+        fn breeding_increases_generation() {
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.mint(KittyId::from(2)), Ok(()));
+            assert_eq!(kitties.mint(KittyId::from(3)), Ok(()));
+            assert_eq!(kitties.generation_of(KittyId::from(1)), Some(0));
+            assert_eq!(kitties.generation_of(KittyId::from(5)), None);
+
+            // A child of two gen-0 parents is gen-1.
+            assert_eq!(kitties.breed(KittyId::from(1), KittyId::from(2)), Ok(KittyId::from(4)));
+            assert_eq!(kitties.generation_of(KittyId::from(4)), Some(1));
+            // A child of a gen-1 and a gen-0 parent is gen-2.
+            assert_eq!(kitties.breed(KittyId::from(4), KittyId::from(3)), Ok(KittyId::from(5)));
+            assert_eq!(kitties.generation_of(KittyId::from(5)), Some(2));
+            // Higher generations cool down longer.
+            assert_eq!(kitties.cooldown_blocks(KittyId::from(3)), BREEDING_COOLDOWNS[0]);
+            assert_eq!(kitties.cooldown_blocks(KittyId::from(5)), BREEDING_COOLDOWNS[2]);
+        }
+
+        #[ink::test]
+        fn royalty_info_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            for (basis_points, royalty) in [(0, 0), (1, 1), (250, 250), (10_000, 10_000)] {
+                assert_eq!(kitties.set_default_royalty(accounts.bob, basis_points), Ok(()));
+                assert_eq!(kitties.royalty_info(KittyId::from(1), 10_000), (accounts.bob, royalty));
+            }
+            // Amounts round down.
+            assert_eq!(kitties.set_default_royalty(accounts.bob, 250), Ok(()));
+            assert_eq!(kitties.royalty_info(KittyId::from(1), 99), (accounts.bob, 2));
+            // Huge prices saturate instead of overflowing.
+            assert_eq!(
+                kitties.royalty_info(KittyId::from(1), u128::MAX),
+                (accounts.bob, u128::MAX / 10_000)
+            );
+        }
+
+        #[ink::test]
+        fn set_default_royalty_rejects_invalid_royalty() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            let royalty = kitties.default_royalty();
+            assert_eq!(
+                kitties.set_default_royalty(accounts.bob, 10_001),
+                Err(Error::InvalidBasisPoints)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                kitties.set_default_royalty(accounts.bob, 100),
+                Err(Error::NotContractOwner)
+            );
+            assert_eq!(kitties.default_royalty(), royalty);
+        }
+
+        #[ink::test]
+        fn supports_interface_works() {
+            // Create a new contract instance.
+            let kitties = Kitties::new_default();
+            assert!(kitties.supports_interface([0x01, 0xff, 0xc9, 0xa7]));
+            assert!(kitties.supports_interface([0x80, 0xac, 0x58, 0xcd]));
+            assert!(kitties.supports_interface([0x2a, 0x55, 0x20, 0x5a]));
+            assert!(!kitties.supports_interface([0xde, 0xad, 0xbe, 0xef]));
+            // ERC-165 requires 0xffffffff to be unsupported.
+            assert!(!kitties.supports_interface([0xff; 4]));
+        }
+
+        #[ink::test]
+        fn breed_cost_scales_with_generation() {
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint_batch(ids(&[1, 2, 3])), Ok(()));
+            // Breeding is free until a fee is set.
+            assert_eq!(kitties.breed_cost(KittyId::from(1), KittyId::from(2)), Some(0));
+            assert_eq!(kitties.breed(KittyId::from(1), KittyId::from(2)), Ok(KittyId::from(4)));
+
+            assert_eq!(kitties.set_breed_fee(100, 2), Ok(()));
+            // Gen-0 parents pay the base fee.
+            assert_eq!(kitties.breed_cost(KittyId::from(1), KittyId::from(3)), Some(100));
+            // A gen-1 parent pays 1 + 2 * 1 times the base fee.
+            assert_eq!(kitties.generation_of(KittyId::from(4)), Some(1));
+            assert_eq!(kitties.breed_cost(KittyId::from(4), KittyId::from(3)), Some(300));
+            assert!(kitties.breed_cost(KittyId::from(1), KittyId::from(3)) < kitties.breed_cost(KittyId::from(4), KittyId::from(3)));
+            // Missing parents have no price.
+            assert_eq!(kitties.breed_cost(KittyId::from(1), KittyId::from(9)), None);
+            // Prices that overflow have no price either.
+            assert_eq!(kitties.set_breed_fee(u128::MAX, 2), Ok(()));
+            assert_eq!(kitties.breed_cost(KittyId::from(4), KittyId::from(3)), None);
+            assert_eq!(
+                kitties.breed(KittyId::from(4), KittyId::from(3)),
+                Err(Error::PriceOverflow)
+            );
+        }
+
+        #[ink::test]
+        fn overflowing_mint_price_is_rejected() {
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.set_mint_price(u128::MAX), Ok(()));
+            // Two kitties cost more than a u128 can hold.
+            assert_eq!(kitties.mint_batch(ids(&[1, 2])), Err(Error::PriceOverflow));
+            assert_eq!(kitties.owner_of(KittyId::from(1)), None);
+        }
+
+        #[ink::test]
+        fn set_ingredient_requires_contract_owner() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.ingredient(), (None, 0));
+            assert_eq!(kitties.set_ingredient(Some(accounts.django), 5), Ok(()));
+            assert_eq!(kitties.ingredient(), (Some(accounts.django), 5));
+            set_caller(accounts.bob);
+            assert_eq!(kitties.set_ingredient(None, 0), Err(Error::NotContractOwner));
+        }
+
+        #[ink::test]
+        fn set_transfer_hook_requires_contract_owner() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.transfer_hook(), None);
+            assert_eq!(kitties.set_transfer_hook(Some(accounts.django)), Ok(()));
+            assert_eq!(kitties.transfer_hook(), Some(accounts.django));
+            set_caller(accounts.bob);
+            assert_eq!(kitties.set_transfer_hook(None), Err(Error::NotContractOwner));
+        }
+
+        #[ink::test]
+        fn zero_id_can_be_minted_by_default() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitties = Kitties::new_default();
+            assert!(!kitties.zero_id_reserved());
+            assert_eq!(kitties.mint(KittyId::from(0)), Ok(()));
+            assert_eq!(kitties.owner_of(KittyId::from(0)), Some(accounts.alice));
+        }
 
         #[ink::test]
-        fn mint_works() {
+        fn reserved_zero_id_cannot_be_minted() {
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
-            // Create a new contract instance.
-            let mut kitties = Kitties::new();
-            // Kitty 1 does not exists.
-            assert_eq!(kitties.owner_of(1), None);
-            // Alice does not owns kitties.
+            let mut kitties = Kitties::new(
+                AccountId::from([0x1; 32]),
+                0,
+                None,
+                AccountId::from([0x0; 32]),
+                0,
+                true,
+            );
+            assert!(kitties.zero_id_reserved());
+            assert_eq!(kitties.mint(KittyId::from(0)), Err(Error::NotAllowed));
+            assert_eq!(kitties.mint_batch(ids(&[1, 0])), Err(Error::NotAllowed));
+            assert_eq!(kitties.owner_of(KittyId::from(1)), None);
             assert_eq!(kitties.balance_of(accounts.alice), 0);
-            // Create kitty Id 1.
-            assert_eq!(kitties.mint(1), Ok(()));
-            // Alice owns 1 kitty.
-            assert_eq!(kitties.balance_of(accounts.alice), 1);
+            // Other ids are unaffected.
+            assert_eq!(kitties.mint_batch(ids(&[1, 2])), Ok(()));
         }
 
         #[ink::test]
-        fn mint_existing_should_fail() {
-            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+        fn lucky_kitties_cost_more() {
+            // Create a new contract instance charging 10 per kitty.
+            let mut kitties = Kitties::new(
+                AccountId::from([0x1; 32]),
+                10,
+                None,
+                AccountId::from([0x0; 32]),
+                0,
+                false,
+            );
+            assert_eq!(kitties.set_lucky_price_multiplier(0), Err(Error::NotAllowed));
+            assert_eq!(kitties.set_lucky_price_multiplier(3), Ok(()));
+            assert_eq!(kitties.mint_price_of(KittyId::from(1)), 10);
+            // Every kitty rolls lucky.
+            assert_eq!(kitties.set_lucky_bps(10_000), Ok(()));
+            assert_eq!(kitties.mint_price_of(KittyId::from(1)), 30);
+            // The lucky roll is over budget, nothing is charged or minted.
+            assert_eq!(kitties.mint_with_max_price(KittyId::from(1), 29), Err(Error::PriceExceedsMax));
+            assert_eq!(kitties.owner_of(KittyId::from(1)), None);
+        }
+
+        #[ink::test]
+        fn set_lucky_bps_rejects_invalid_chance() {
             // Create a new contract instance.
-            let mut kitties = Kitties::new();
-            // Create kitty Id 1.
-            assert_eq!(kitties.mint(1), Ok(()));
-            // The first Transfer event takes place
-            assert_eq!(1, ink::env::test::recorded_events().count());
-            // Alice owns 1 kitty.
-            assert_eq!(kitties.balance_of(accounts.alice), 1);
-            // Alice owns kitty Id 1.
-            assert_eq!(kitties.owner_of(1), Some(accounts.alice));
-            // Cannot create  kitty Id if it exists.
-            // Bob cannot own kitty Id 1.
-            assert_eq!(kitties.mint(1), Err(Error::TokenExists));
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.set_lucky_bps(10_001), Err(Error::InvalidBasisPoints));
+            assert_eq!(kitties.lucky_bps(), 0);
         }
 
         #[ink::test]
-        fn transfer_works() {
+        fn mint_up_to_max_supply_works() {
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
-            // Create a new contract instance.
-            let mut kitties = Kitties::new();
-            // Create kitty Id 1 for Alice
-            assert_eq!(kitties.mint(1), Ok(()));
-            // Alice owns kitty 1
-            assert_eq!(kitties.balance_of(accounts.alice), 1);
-            // Bob does not owns any kitty
-            assert_eq!(kitties.balance_of(accounts.bob), 0);
-            // The first Transfer event takes place
-            assert_eq!(1, ink::env::test::recorded_events().count());
-            // Alice transfers kitty 1 to Bob
-            assert_eq!(kitties.transfer(accounts.bob, 1), Ok(()));
-            // The second Transfer event takes place
-            assert_eq!(2, ink::env::test::recorded_events().count());
-            // Bob owns kitty 1
-            assert_eq!(kitties.balance_of(accounts.bob), 1);
+            // Create a new contract instance capped at 2 kitties.
+            let mut kitties = Kitties::new_default();
+            kitties.max_supply = Some(2);
+            assert_eq!(kitties.max_supply(), Some(2));
+            // Create kitty Id 1 and 2.
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.mint(KittyId::from(2)), Ok(()));
+            // The cap is reached.
+            assert_eq!(kitties.mint(KittyId::from(3)), Err(Error::MaxSupplyReached));
+            assert_eq!(kitties.owner_of(KittyId::from(3)), None);
+            assert_eq!(kitties.balance_of(accounts.alice), 2);
         }
 
         #[ink::test]
-        fn invalid_transfer_should_fail() {
+        fn mint_progress_works() {
+            // Create a new contract instance capped at 5 kitties.
+            let mut kitties = Kitties::new_default();
+            kitties.max_supply = Some(5);
+            assert_eq!(kitties.mint_progress(), (0, Some(5)));
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.mint(KittyId::from(2)), Ok(()));
+            assert_eq!(kitties.mint(KittyId::from(3)), Ok(()));
+            assert_eq!(kitties.mint_progress(), (3, Some(5)));
+            // A burned kitty still counts as minted.
+            assert_eq!(kitties.burn(KittyId::from(2)), Ok(()));
+            assert_eq!(kitties.mint_progress(), (3, Some(5)));
+            assert_eq!(kitties.mint(KittyId::from(4)), Ok(()));
+            assert_eq!(kitties.mint_progress(), (4, Some(5)));
+            // Without a cap there is no maximum.
+            kitties.max_supply = None;
+            assert_eq!(kitties.mint_progress(), (4, None));
+        }
+
+        #[ink::test]
+        fn burn_frees_max_supply_slot() {
+            // Create a new contract instance capped at 1 kitty.
+            let mut kitties = Kitties::new_default();
+            kitties.max_supply = Some(1);
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.mint(KittyId::from(2)), Err(Error::MaxSupplyReached));
+            // Burning kitty 1 frees up its slot.
+            assert_eq!(kitties.burn(KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.mint(KittyId::from(2)), Ok(()));
+        }
+
+        #[ink::test]
+        fn ownership_root_works() {
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             // Create a new contract instance.
-            let mut kitties = Kitties::new();
-            // Transfer kitty fails if it does not exists.
-            assert_eq!(
-                kitties.transfer(accounts.bob, 2),
-                Err(Error::TokenNotFound)
-            );
-            // Kitty Id 2 does not exists.
-            assert_eq!(kitties.owner_of(2), None);
-            // Create kitty Id 2.
-            assert_eq!(kitties.mint(2), Ok(()));
-            // Alice owns 1 kitty.
-            assert_eq!(kitties.balance_of(accounts.alice), 1);
-            // Kitty Id 2 is owned by Alice.
-            assert_eq!(kitties.owner_of(2), Some(accounts.alice));
-            // Set Bob as caller
-            set_caller(accounts.bob);
-            // Bob cannot transfer not owned kitties.
-            assert_eq!(
-                kitties.transfer(accounts.eve, 2),
-                Err(Error::NotApproved)
-            );
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.ownership_root(vec![]), [0; 32]);
+            assert_eq!(kitties.mint_batch(ids(&[1, 2, 3])), Ok(()));
+
+            // The root only depends on the input set and the owners.
+            let root = kitties.ownership_root(ids(&[1, 2, 3]));
+            assert_eq!(kitties.ownership_root(ids(&[1, 2, 3])), root);
+            assert_ne!(kitties.ownership_root(ids(&[3, 2, 1])), root);
+            assert_ne!(kitties.ownership_root(ids(&[1, 2])), root);
+
+            // A single leaf is the root.
+            let id: KittyId = KittyId::from(1);
+            let mut leaf = <Blake2x256 as HashOutput>::Type::default();
+            ink::env::hash_encoded::<Blake2x256, _>(&(id, Some(accounts.alice)), &mut leaf);
+            assert_eq!(kitties.ownership_root(vec![id]), leaf);
+
+            // A new owner changes the root.
+            assert_eq!(kitties.transfer(accounts.bob, KittyId::from(2)), Ok(()));
+            assert_ne!(kitties.ownership_root(ids(&[1, 2, 3])), root);
         }
 
         #[ink::test]
-        fn approved_transfer_works() {
+        fn tokens_of_owner_pages_work() {
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             // Create a new contract instance.
-            let mut kitties = Kitties::new();
-            // Create kitty Id 1.
-            assert_eq!(kitties.mint(1), Ok(()));
-            // Kitty Id 1 is owned by Alice.
-            assert_eq!(kitties.owner_of(1), Some(accounts.alice));
-            // Approve kitty Id 1 transfer for Bob on behalf of Alice.
-            assert_eq!(kitties.approve(accounts.bob, 1), Ok(()));
-            // Set Bob as caller
-            set_caller(accounts.bob);
-            // Bob transfers kitty Id 1 from Alice to Eve.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.mint_batch(ids(&[1, 2, 3, 4, 5])), Ok(()));
+            assert_eq!(kitties.tokens_of_owner(accounts.alice, 0, 2), ids(&[1, 2]));
+            assert_eq!(kitties.tokens_of_owner(accounts.alice, 2, 2), ids(&[3, 4]));
+            assert_eq!(kitties.tokens_of_owner(accounts.alice, 4, 10), ids(&[5]));
+            assert_eq!(kitties.tokens_of_owner(accounts.alice, 5, 10), Vec::<KittyId>::new());
             assert_eq!(
-                kitties.transfer_from(accounts.alice, accounts.eve, 1),
-                Ok(())
+                kitties.tokens_of_owner(accounts.alice, u32::MAX, u32::MAX),
+                Vec::<KittyId>::new()
             );
-            // KittyId 3 is owned by Eve.
-            assert_eq!(kitties.owner_of(1), Some(accounts.eve));
-            // Alice does not owns kitties.
-            assert_eq!(kitties.balance_of(accounts.alice), 0);
-            // Bob does not owns kitties.
-            assert_eq!(kitties.balance_of(accounts.bob), 0);
-            // Eve owns 1 kitty.
-            assert_eq!(kitties.balance_of(accounts.eve), 1);
+            assert_eq!(kitties.token_of_owner_by_index(accounts.alice, 4), Some(KittyId::from(5)));
+            // Bob has no kitties.
+            assert_eq!(kitties.tokens_of_owner(accounts.bob, 0, 10), Vec::<KittyId>::new());
+            // The last kitty of Alice takes the place of a kitty she gives away.
+            assert_eq!(kitties.transfer(accounts.bob, KittyId::from(2)), Ok(()));
+            assert_eq!(kitties.tokens_of_owner(accounts.alice, 0, 10), ids(&[1, 5, 3, 4]));
+            assert_eq!(kitties.tokens_of_owner(accounts.bob, 0, 10), ids(&[2]));
+            assert_eq!(kitties.burn(KittyId::from(4)), Ok(()));
+            assert_eq!(kitties.tokens_of_owner(accounts.alice, 0, 10), ids(&[1, 5, 3]));
+            assert_eq!(kitties.token_of_owner_by_index(accounts.alice, 3), None);
         }
 
         #[ink::test]
-        fn approved_for_all_works() {
+        fn top_holders_works() {
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             // Create a new contract instance.
-            let mut kitties = Kitties::new();
-            // Create kitty Id 1.
-            assert_eq!(kitties.mint(1), Ok(()));
-            // Create kitty Id 2.
-            assert_eq!(kitties.mint(2), Ok(()));
-            // Alice owns 2 kitties.
-            assert_eq!(kitties.balance_of(accounts.alice), 2);
-            // Approve kitty Id 1 transfer for Bob on behalf of Alice.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.top_holders(3), vec![]);
+            // Alice keeps 1 kitty, Bob gets 3 and Charlie 2.
+            assert_eq!(kitties.mint_batch(ids(&[1, 2, 3, 4, 5, 6, 7])), Ok(()));
+            assert_eq!(kitties.transfer_batch(accounts.bob, ids(&[2, 3, 4])), Ok(()));
+            assert_eq!(kitties.transfer_batch(accounts.charlie, ids(&[5, 6])), Ok(()));
+            // Django held a kitty but gave it back.
+            assert_eq!(kitties.transfer(accounts.django, KittyId::from(7)), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(kitties.transfer(accounts.alice, KittyId::from(7)), Ok(()));
+
             assert_eq!(
-                kitties.set_approval_for_all(accounts.bob, true),
-                Ok(())
+                kitties.top_holders(3),
+                vec![(accounts.bob, 3), (accounts.alice, 2), (accounts.charlie, 2)]
             );
-            // Bob is an approved operator for Alice
-            assert!(kitties.is_approved_for_all(accounts.alice, accounts.bob));
-            // Set Bob as caller
-            set_caller(accounts.bob);
-            // Bob transfers kitty Id 1 from Alice to Eve.
+            assert_eq!(kitties.top_holders(1), vec![(accounts.bob, 3)]);
+            // Accounts without kitties are left out.
+            assert_eq!(kitties.top_holders(10).len(), 3);
+            // Alice got kitty 7 back, but is only indexed once.
+            assert_eq!(kitties.holder_count, 4);
+        }
+
+        #[ink::test]
+        fn max_owned_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance where an account can own 2 kitties.
+            let mut kitties = Kitties::new_default();
+            assert_eq!(kitties.set_max_owned(Some(2)), Ok(()));
+            assert_eq!(kitties.max_owned(), Some(2));
+            assert_eq!(kitties.mint_batch(ids(&[1, 2, 3])), Err(Error::HoldingLimitReached));
+            assert_eq!(kitties.mint_batch(ids(&[1, 2])), Ok(()));
+            // Alice owns 2 kitties already.
+            assert_eq!(kitties.mint(KittyId::from(3)), Err(Error::HoldingLimitReached));
+            // Bob can receive kitties up to the cap.
+            assert_eq!(kitties.transfer_batch(accounts.bob, ids(&[1, 2])), Ok(()));
+            assert_eq!(kitties.balance_of(accounts.bob), 2);
+            assert_eq!(kitties.mint(KittyId::from(3)), Ok(()));
+            // One more is over the cap.
+            assert_eq!(kitties.transfer(accounts.bob, KittyId::from(3)), Err(Error::HoldingLimitReached));
             assert_eq!(
-                kitties.transfer_from(accounts.alice, accounts.eve, 1),
-                Ok(())
+                kitties.transfer_distribution(vec![(KittyId::from(3), accounts.bob)]),
+                Err(Error::HoldingLimitReached)
             );
-            // KittyId 1 is owned by Eve.
-            assert_eq!(kitties.owner_of(1), Some(accounts.eve));
-            // Alice owns 1 kitty.
-            assert_eq!(kitties.balance_of(accounts.alice), 1);
-            // Bob transfers kitty Id 2 from Alice to Eve.
+            assert_eq!(kitties.owner_of(KittyId::from(3)), Some(accounts.alice));
+            // Without a cap Bob can own more.
+            assert_eq!(kitties.set_max_owned(None), Ok(()));
+            assert_eq!(kitties.transfer(accounts.bob, KittyId::from(3)), Ok(()));
+            assert_eq!(kitties.balance_of(accounts.bob), 3);
+        }
+
+        #[ink::test]
+        fn recipient_allowlist_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // Create a new contract instance.
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // Kitty 1 can only be transferred to Bob.
             assert_eq!(
-                kitties.transfer_from(accounts.alice, accounts.eve, 2),
+                kitties.set_recipient_allowlist(KittyId::from(1), vec![accounts.bob]),
                 Ok(())
             );
-            // Bob does not own kitties.
-            assert_eq!(kitties.balance_of(accounts.bob), 0);
-            // Eve owns 2 kitties.
-            assert_eq!(kitties.balance_of(accounts.eve), 2);
-            // Remove operator approval for Bob on behalf of Alice.
-            set_caller(accounts.alice);
+            assert_eq!(kitties.recipient_allowlist(KittyId::from(1)), vec![accounts.bob]);
+            // Eve is not on the allowlist.
             assert_eq!(
-                kitties.set_approval_for_all(accounts.bob, false),
-                Ok(())
+                kitties.transfer(accounts.eve, KittyId::from(1)),
+                Err(Error::RecipientNotAllowed)
             );
-            // Bob is not an approved operator for Alice.
-            assert!(!kitties.is_approved_for_all(accounts.alice, accounts.bob));
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.alice));
+            // Bob is on the allowlist.
+            assert_eq!(kitties.transfer(accounts.bob, KittyId::from(1)), Ok(()));
+            assert_eq!(kitties.owner_of(KittyId::from(1)), Some(accounts.bob));
         }
 
         #[ink::test]
-        fn not_approved_transfer_should_fail() {
+        fn empty_recipient_allowlist_is_unrestricted() {
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             // Create a new contract instance.
-            let mut kitties = Kitties::new();
-            // Create kitty Id 1.
-            assert_eq!(kitties.mint(1), Ok(()));
-            // Alice owns 1 kitty.
-            assert_eq!(kitties.balance_of(accounts.alice), 1);
-            // Bob does not owns kitties.
-            assert_eq!(kitties.balance_of(accounts.bob), 0);
-            // Eve does not owns kitties.
-            assert_eq!(kitties.balance_of(accounts.eve), 0);
-            // Set Eve as caller
+            let mut kitties = Kitties::new_default();
+            // Create kitty Id 1 for Alice
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            assert_eq!(
+                kitties.set_recipient_allowlist(KittyId::from(1), vec![accounts.bob]),
+                Ok(())
+            );
+            // Eve cannot restrict a kitty she does not own.
             set_caller(accounts.eve);
-            // Eve is not an approved operator by Alice.
             assert_eq!(
-                kitties.transfer_from(accounts.alice, accounts.frank, 1),
-                Err(Error::NotApproved)
+                kitties.set_recipient_allowlist(KittyId::from(1), Vec::new()),
+                Err(Error::NotOwner)
             );
-            // Alice owns 1 kitty.
-            assert_eq!(kitties.balance_of(accounts.alice), 1);
-            // Bob does not owns kitties.
-            assert_eq!(kitties.balance_of(accounts.bob), 0);
-            // Eve does not owns kitties.
-            assert_eq!(kitties.balance_of(accounts.eve), 0);
+            // Alice lifts the restriction.
+            set_caller(accounts.alice);
+            assert_eq!(kitties.set_recipient_allowlist(KittyId::from(1), Vec::new()), Ok(()));
+            assert_eq!(kitties.transfer(accounts.eve, KittyId::from(1)), Ok(()));
         }
 
         #[ink::test]
-        fn burn_works() {
+        fn withdraw_requires_contract_owner() {
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             // Create a new contract instance.
-            let mut kitties = Kitties::new();
-            // Create kitty Id 1 for Alice
-            assert_eq!(kitties.mint(1), Ok(()));
-            // Alice owns 1 kitty.
-            assert_eq!(kitties.balance_of(accounts.alice), 1);
-            // Alice owns kitty Id 1.
-            assert_eq!(kitties.owner_of(1), Some(accounts.alice));
-            // Destroy kitty Id 1.
-            assert_eq!(kitties.burn(1), Ok(()));
-            // Alice does not owns kitties.
-            assert_eq!(kitties.balance_of(accounts.alice), 0);
-            // Kitty Id 1 does not exists
-            assert_eq!(kitties.owner_of(1), None);
+            let mut kitties = Kitties::new_default();
+            // Bob is not the contract owner.
+            set_caller(accounts.bob);
+            assert_eq!(kitties.withdraw(10), Err(Error::NotContractOwner));
         }
 
         #[ink::test]
-        fn burn_fails_token_not_found() {
+        fn set_mint_price_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             // Create a new contract instance.
-            let mut kitties = Kitties::new();
-            // Try burning a non existent kitty
-            assert_eq!(kitties.burn(1), Err(Error::TokenNotFound));
+            let mut kitties = Kitties::new_default();
+            // Bob is not the contract owner.
+            set_caller(accounts.bob);
+            assert_eq!(kitties.set_mint_price(20), Err(Error::NotContractOwner));
+            assert_eq!(kitties.mint_price(), 0);
+            // Alice updates the mint price.
+            set_caller(accounts.alice);
+            assert_eq!(kitties.set_mint_price(20), Ok(()));
+            assert_eq!(kitties.mint_price(), 20);
+            assert_eq!(1, ink::env::test::recorded_events().count());
         }
 
         #[ink::test]
-        fn burn_fails_not_owner() {
+        fn can_operate_works() {
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             // Create a new contract instance.
-            let mut kitties = Kitties::new();
+            let mut kitties = Kitties::new_default();
             // Create kitty Id 1 for Alice
-            assert_eq!(kitties.mint(1), Ok(()));
-            // Try burning this kitty with a different account
-            set_caller(accounts.eve);
-            assert_eq!(kitties.burn(1), Err(Error::NotOwner));
+            assert_eq!(kitties.mint(KittyId::from(1)), Ok(()));
+            // The owner can operate its own kitty.
+            assert!(kitties.can_operate(accounts.alice, accounts.alice, KittyId::from(1)));
+            // Nobody else can before being approved.
+            assert!(!kitties.can_operate(accounts.alice, accounts.bob, KittyId::from(1)));
+            assert!(!kitties.can_operate(accounts.alice, accounts.charlie, KittyId::from(1)));
+            // Bob is approved for kitty 1.
+            assert_eq!(kitties.approve(accounts.bob, KittyId::from(1)), Ok(()));
+            assert!(kitties.can_operate(accounts.alice, accounts.bob, KittyId::from(1)));
+            // Charlie is an operator for all kitties of Alice.
+            assert_eq!(kitties.set_approval_for_all(accounts.charlie, true), Ok(()));
+            assert!(kitties.can_operate(accounts.alice, accounts.charlie, KittyId::from(1)));
+            // Eve is not approved at all.
+            assert!(!kitties.can_operate(accounts.alice, accounts.eve, KittyId::from(1)));
+            // Nobody can operate on behalf of someone who does not own the kitty.
+            assert!(!kitties.can_operate(accounts.eve, accounts.eve, KittyId::from(1)));
+            // Nobody can operate a kitty that does not exist.
+            assert!(!kitties.can_operate(accounts.alice, accounts.alice, KittyId::from(2)));
+            // Revoking the operator denies Charlie again.
+            assert_eq!(kitties.set_approval_for_all(accounts.charlie, false), Ok(()));
+            assert!(!kitties.can_operate(accounts.alice, accounts.charlie, KittyId::from(1)));
         }
 
         fn set_caller(sender: AccountId) {
             ink::env::test::set_caller::<ink::env::DefaultEnvironment>(sender);
         }
     }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink_e2e::build_message;
+        use kitty_coin::KittyCoinRef;
+        use sale_hook_mock::SaleHookMockRef;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+        type Event = <Kitties as ::ink::reflect::ContractEventBase>::Type;
+
+        const MINT_PRICE: u128 = 10;
+
+        /// Instantiates KittyCoin and Kitties, and lets Alice mint kitty `id`.
+        async fn setup(
+            client: &mut ink_e2e::Client<ink_e2e::PolkadotConfig, ink::env::DefaultEnvironment>,
+            id: KittyId,
+        ) -> (AccountId, AccountId) {
+            let coin_constructor = KittyCoinRef::new(10_000);
+            let coin_account_id = client
+                .instantiate("kitty_coin", &ink_e2e::alice(), coin_constructor, 0, None)
+                .await
+                .expect("instantiate kitty_coin failed")
+                .account_id;
+
+            let constructor = KittiesRef::new(
+                coin_account_id,
+                MINT_PRICE,
+                None,
+                ink_e2e::account_id(ink_e2e::AccountKeyring::Alice),
+                0,
+                false,
+            );
+            let kitties_account_id = client
+                .instantiate("kitties", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate kitties failed")
+                .account_id;
+
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(kitties_account_id, MINT_PRICE));
+            client
+                .call(&ink_e2e::alice(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+
+            let mint_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.mint(id));
+            client
+                .call(&ink_e2e::alice(), mint_msg, 0, None)
+                .await
+                .expect("mint failed");
+
+            (coin_account_id, kitties_account_id)
+        }
+
+        #[ink_e2e::test(additional_contracts = "../kitty_coin/Cargo.toml")]
+        async fn safe_transfer_to_non_receiver_rolls_back(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id) =
+                setup(&mut client, KittyId::from(1)).await;
+            let alice_acc = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+
+            // KittyCoin is a contract without an `on_erc721_received` hook.
+            let safe_transfer_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| {
+                    kitties.safe_transfer_from(
+                        alice_acc,
+                        coin_account_id,
+                        KittyId::from(1),
+                        Vec::new(),
+                    )
+                });
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &safe_transfer_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), Err(Error::SafeTransferCheckFailed));
+
+            let res = client.call(&ink_e2e::alice(), safe_transfer_msg, 0, None).await;
+            assert!(res.is_err());
+
+            // Alice still owns the kitty.
+            let owner_of_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.owner_of(KittyId::from(1)));
+            let owner = client
+                .call_dry_run(&ink_e2e::alice(), &owner_of_msg, 0, None)
+                .await;
+            assert_eq!(owner.return_value(), Some(alice_acc));
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(additional_contracts = "../kitty_coin/Cargo.toml")]
+        async fn withdraw_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id) =
+                setup(&mut client, KittyId::from(1)).await;
+            let alice_acc = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+
+            // Alice mints a second kitty.
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(kitties_account_id, MINT_PRICE));
+            client
+                .call(&ink_e2e::alice(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+            let mint_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.mint(KittyId::from(2)));
+            client
+                .call(&ink_e2e::alice(), mint_msg, 0, None)
+                .await
+                .expect("mint failed");
+
+            // Bob is not the contract owner.
+            let withdraw_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.withdraw(2 * MINT_PRICE));
+            let res = client.call_dry_run(&ink_e2e::bob(), &withdraw_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::NotContractOwner));
+
+            // Alice withdraws the revenue of both mints.
+            client
+                .call(&ink_e2e::alice(), withdraw_msg, 0, None)
+                .await
+                .expect("withdraw failed");
+
+            let balance_of_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.balance_of(kitties_account_id));
+            let balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of_msg, 0, None)
+                .await;
+            assert_eq!(balance.return_value(), 0);
+
+            let balance_of_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.balance_of(alice_acc));
+            let balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of_msg, 0, None)
+                .await;
+            assert_eq!(balance.return_value(), 10_000);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(additional_contracts = "../kitty_coin/Cargo.toml")]
+        async fn updated_mint_price_is_charged(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id) =
+                setup(&mut client, KittyId::from(1)).await;
+
+            // Alice doubles the mint price.
+            let set_mint_price_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.set_mint_price(2 * MINT_PRICE));
+            client
+                .call(&ink_e2e::alice(), set_mint_price_msg, 0, None)
+                .await
+                .expect("set_mint_price failed");
+
+            // The old allowance no longer covers a mint.
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(kitties_account_id, MINT_PRICE));
+            client
+                .call(&ink_e2e::alice(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+            let mint_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.mint(KittyId::from(2)));
+            let res = client.call_dry_run(&ink_e2e::alice(), &mint_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::CoinTransferFail));
+
+            // The new price is charged on the next mint.
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(kitties_account_id, 2 * MINT_PRICE));
+            client
+                .call(&ink_e2e::alice(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+            client
+                .call(&ink_e2e::alice(), mint_msg, 0, None)
+                .await
+                .expect("mint failed");
+
+            let balance_of_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.balance_of(kitties_account_id));
+            let balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of_msg, 0, None)
+                .await;
+            assert_eq!(balance.return_value(), 3 * MINT_PRICE);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(additional_contracts = "../kitty_coin/Cargo.toml")]
+        async fn remaining_mints_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id) =
+                setup(&mut client, KittyId::from(1)).await;
+            let alice_acc = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let remaining_mints_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.remaining_mints(alice_acc));
+
+            // The allowance was used up by the first mint.
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &remaining_mints_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), 0);
+
+            // 35 coins cover 3 kitties at 10 coins.
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(kitties_account_id, 35));
+            client
+                .call(&ink_e2e::alice(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &remaining_mints_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), 3);
+
+            // And 5 kitties at 7 coins.
+            let set_mint_price_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.set_mint_price(7));
+            client
+                .call(&ink_e2e::alice(), set_mint_price_msg, 0, None)
+                .await
+                .expect("set_mint_price failed");
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &remaining_mints_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), 5);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(additional_contracts = "../kitty_coin/Cargo.toml")]
+        async fn lucky_mint_charges_more(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id) =
+                setup(&mut client, KittyId::from(1)).await;
+            let alice_acc = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+
+            // Every kitty rolls lucky and costs three times the mint price.
+            let set_lucky_bps_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.set_lucky_bps(10_000));
+            client
+                .call(&ink_e2e::alice(), set_lucky_bps_msg, 0, None)
+                .await
+                .expect("set_lucky_bps failed");
+            let set_multiplier_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.set_lucky_price_multiplier(3));
+            client
+                .call(&ink_e2e::alice(), set_multiplier_msg, 0, None)
+                .await
+                .expect("set_lucky_price_multiplier failed");
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(kitties_account_id, 3 * MINT_PRICE));
+            client
+                .call(&ink_e2e::alice(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+
+            // The lucky kitty is over a budget of the plain mint price.
+            let mint_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.mint_with_max_price(KittyId::from(2), MINT_PRICE));
+            let res = client.call_dry_run(&ink_e2e::alice(), &mint_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::PriceExceedsMax));
+
+            let mint_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.mint_with_max_price(KittyId::from(2), 3 * MINT_PRICE));
+            let mint_result = client
+                .call(&ink_e2e::alice(), mint_msg, 0, None)
+                .await
+                .expect("mint failed");
+
+            // The Minted event tells the tripled price that was charged.
+            let minted_price = mint_result
+                .events
+                .iter()
+                .filter_map(|event| event.ok())
+                .filter(|event| {
+                    event.pallet_name() == "Contracts" && event.variant_name() == "ContractEmitted"
+                })
+                .filter_map(|event| {
+                    let (_, data) = <(AccountId, Vec<u8>) as scale::Decode>::decode(
+                        &mut event.field_bytes(),
+                    )
+                    .ok()?;
+                    match <Event as scale::Decode>::decode(&mut &data[..]).ok()? {
+                        Event::Minted(Minted { price, .. }) => Some(price),
+                        _ => None,
+                    }
+                })
+                .next();
+            assert_eq!(minted_price, Some(3 * MINT_PRICE));
+
+            let balance_of_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.balance_of(alice_acc));
+            let balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of_msg, 0, None)
+                .await;
+            assert_eq!(balance.return_value(), 10_000 - MINT_PRICE - 3 * MINT_PRICE);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(additional_contracts = "../kitty_coin/Cargo.toml")]
+        async fn mint_burns_ingredient(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id) =
+                setup(&mut client, KittyId::from(1)).await;
+
+            // A second coin serves as the ingredient, 5 of it are burned for every kitty.
+            let ingredient_constructor = KittyCoinRef::new(1_000);
+            let ingredient_account_id = client
+                .instantiate("kitty_coin", &ink_e2e::alice(), ingredient_constructor, 0, None)
+                .await
+                .expect("instantiate ingredient failed")
+                .account_id;
+            let set_ingredient_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.set_ingredient(Some(ingredient_account_id), 5));
+            client
+                .call(&ink_e2e::alice(), set_ingredient_msg, 0, None)
+                .await
+                .expect("set_ingredient failed");
+
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(kitties_account_id, MINT_PRICE));
+            client
+                .call(&ink_e2e::alice(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+            let approve_ingredient_msg = build_message::<KittyCoinRef>(ingredient_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(kitties_account_id, 5));
+            client
+                .call(&ink_e2e::alice(), approve_ingredient_msg, 0, None)
+                .await
+                .expect("approve failed");
+            let mint_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.mint(KittyId::from(2)));
+            client
+                .call(&ink_e2e::alice(), mint_msg, 0, None)
+                .await
+                .expect("mint failed");
+
+            let alice_acc = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let balance_of_msg = build_message::<KittyCoinRef>(ingredient_account_id.clone())
+                .call(|kitty_coin| kitty_coin.balance_of(alice_acc));
+            let balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of_msg, 0, None)
+                .await;
+            assert_eq!(balance.return_value(), 1_000 - 5);
+
+            // Breeding burns the ingredient like minting.
+            let approve_ingredient_msg = build_message::<KittyCoinRef>(ingredient_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(kitties_account_id, 5));
+            client
+                .call(&ink_e2e::alice(), approve_ingredient_msg, 0, None)
+                .await
+                .expect("approve failed");
+            let breed_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.breed(KittyId::from(1), KittyId::from(2)));
+            client
+                .call(&ink_e2e::alice(), breed_msg, 0, None)
+                .await
+                .expect("breed failed");
+            let balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of_msg, 0, None)
+                .await;
+            assert_eq!(balance.return_value(), 1_000 - 10);
+
+            // Bob can pay the mint price but holds no ingredient.
+            let bob_acc = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let transfer_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.transfer(bob_acc, MINT_PRICE));
+            client
+                .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                .await
+                .expect("transfer failed");
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(kitties_account_id, MINT_PRICE));
+            client
+                .call(&ink_e2e::bob(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+            let mint_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.mint(KittyId::from(4)));
+            let res = client.call_dry_run(&ink_e2e::bob(), &mint_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::IngredientBurnFail));
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../sale_hook_mock/Cargo.toml"
+        )]
+        async fn market_is_notified_of_transfers(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let (_, kitties_account_id) = setup(&mut client, KittyId::from(1)).await;
+            let hook_account_id = set_hook(&mut client, kitties_account_id, true, false).await;
+            let alice_acc = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let bob_acc = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+
+            let transfer_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.transfer(bob_acc, KittyId::from(1)));
+            client
+                .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                .await
+                .expect("transfer failed");
+
+            let last_transfer_msg = build_message::<SaleHookMockRef>(hook_account_id.clone())
+                .call(|hook| hook.last_transfer());
+            let last_transfer = client
+                .call_dry_run(&ink_e2e::alice(), &last_transfer_msg, 0, None)
+                .await;
+            assert_eq!(
+                last_transfer.return_value(),
+                Some((KittyId::from(1), alice_acc, bob_acc))
+            );
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../sale_hook_mock/Cargo.toml"
+        )]
+        async fn market_is_notified_of_burns(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (_, kitties_account_id) = setup(&mut client, KittyId::from(1)).await;
+            let hook_account_id = set_hook(&mut client, kitties_account_id, true, false).await;
+            let alice_acc = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+
+            let burn_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.burn(KittyId::from(1)));
+            client
+                .call(&ink_e2e::alice(), burn_msg, 0, None)
+                .await
+                .expect("burn failed");
+
+            let last_transfer_msg = build_message::<SaleHookMockRef>(hook_account_id.clone())
+                .call(|hook| hook.last_transfer());
+            let last_transfer = client
+                .call_dry_run(&ink_e2e::alice(), &last_transfer_msg, 0, None)
+                .await;
+            assert_eq!(
+                last_transfer.return_value(),
+                Some((KittyId::from(1), alice_acc, AccountId::from([0x0; 32])))
+            );
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../sale_hook_mock/Cargo.toml"
+        )]
+        async fn reverting_transfer_hook_does_not_block_transfer(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let (_, kitties_account_id) = setup(&mut client, KittyId::from(1)).await;
+            set_hook(&mut client, kitties_account_id, true, true).await;
+            let bob_acc = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+
+            let transfer_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.transfer(bob_acc, KittyId::from(1)));
+            client
+                .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                .await
+                .expect("transfer failed");
+
+            let owner_of_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.owner_of(KittyId::from(1)));
+            let owner = client
+                .call_dry_run(&ink_e2e::alice(), &owner_of_msg, 0, None)
+                .await;
+            assert_eq!(owner.return_value(), Some(bob_acc));
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../sale_hook_mock/Cargo.toml"
+        )]
+        async fn transfer_hook_is_notified(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (_, kitties_account_id) = setup(&mut client, KittyId::from(1)).await;
+            let hook_account_id = set_hook(&mut client, kitties_account_id, false, false).await;
+            let alice_acc = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let bob_acc = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+
+            let transfer_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.transfer(bob_acc, KittyId::from(1)));
+            client
+                .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                .await
+                .expect("transfer failed");
+
+            let last_transfer_msg = build_message::<SaleHookMockRef>(hook_account_id.clone())
+                .call(|hook| hook.last_transfer());
+            let last_transfer = client
+                .call_dry_run(&ink_e2e::alice(), &last_transfer_msg, 0, None)
+                .await;
+            assert_eq!(
+                last_transfer.return_value(),
+                Some((KittyId::from(1), alice_acc, bob_acc))
+            );
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../sale_hook_mock/Cargo.toml"
+        )]
+        async fn reverting_transfer_hook_is_not_fatal(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let (_, kitties_account_id) = setup(&mut client, KittyId::from(1)).await;
+            set_hook(&mut client, kitties_account_id, false, true).await;
+            let bob_acc = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+
+            let transfer_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.transfer(bob_acc, KittyId::from(1)));
+            client
+                .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                .await
+                .expect("transfer failed");
+
+            let owner_of_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.owner_of(KittyId::from(1)));
+            let owner = client
+                .call_dry_run(&ink_e2e::alice(), &owner_of_msg, 0, None)
+                .await;
+            assert_eq!(owner.return_value(), Some(bob_acc));
+
+            Ok(())
+        }
+
+        /// Instantiates a mock hook, reverting every notification if `revert` is set,
+        /// and registers it on the kitties contract as the market or, with `as_market`
+        /// unset, as the transfer hook.
+        async fn set_hook(
+            client: &mut ink_e2e::Client<ink_e2e::PolkadotConfig, ink::env::DefaultEnvironment>,
+            kitties_account_id: AccountId,
+            as_market: bool,
+            revert: bool,
+        ) -> AccountId {
+            let hook_constructor = SaleHookMockRef::new(revert);
+            let hook_account_id = client
+                .instantiate("sale_hook_mock", &ink_e2e::alice(), hook_constructor, 0, None)
+                .await
+                .expect("instantiate sale_hook_mock failed")
+                .account_id;
+
+            let set_hook_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| {
+                    if as_market {
+                        kitties.set_market(Some(hook_account_id))
+                    } else {
+                        kitties.set_transfer_hook(Some(hook_account_id))
+                    }
+                });
+            client
+                .call(&ink_e2e::alice(), set_hook_msg, 0, None)
+                .await
+                .expect("setting the hook failed");
+
+            hook_account_id
+        }
+    }
 }
@@ -3,15 +3,30 @@ pub use kitty_coin::{KittyCoin, KittyCoinRef};
 
 #[ink::contract]
 mod kitty_coin {
-    use ink::storage::Mapping;
-    use trait_erc20::{Error, Result, TERC20};
+    use ink::{
+        env::hash::{Blake2x256, HashOutput},
+        prelude::{string::String, vec::Vec},
+        storage::Mapping,
+    };
+    use trait_erc20::{Error, Result, TERC20Metadata, TERC20};
 
     #[ink(storage)]
-    #[derive(Default)]
     pub struct KittyCoin {
         total_supply: Balance,
         balances: Mapping<AccountId, Balance>,
         allowances: Mapping<(AccountId, AccountId), Balance>,
+        /// Account allowed to mint new coins.
+        owner: AccountId,
+        name: String,
+        symbol: String,
+        /// Number of decimals wallets use to display amounts.
+        decimals: u8,
+        /// Maximum total supply minting can reach.
+        cap: Option<Balance>,
+        /// Number of permits used by every owner, each permit is signed for the next one.
+        nonces: Mapping<AccountId, u64>,
+        /// Whether transfers and approvals are stopped.
+        paused: bool,
     }
 
     #[ink(event)]
@@ -33,8 +48,44 @@ mod kitty_coin {
     }
 
     impl KittyCoin {
+        /// Creates the coin named "KittyCoin" with symbol "KTC" and no decimals, so amounts
+        /// are whole coins. Minting is uncapped.
         #[ink(constructor)]
         pub fn new(total_supply: Balance) -> Self {
+            Self::init(
+                total_supply,
+                String::from("KittyCoin"),
+                String::from("KTC"),
+                0,
+                None,
+            )
+        }
+
+        /// With a `cap` minting fails once the total supply would exceed it.
+        ///
+        /// Fails with `CapExceeded` if `total_supply` already exceeds the `cap`.
+        #[ink(constructor)]
+        pub fn with_metadata(
+            total_supply: Balance,
+            name: String,
+            symbol: String,
+            decimals: u8,
+            cap: Option<Balance>,
+        ) -> Result<Self> {
+            if cap.is_some_and(|cap| total_supply > cap) {
+                return Err(Error::CapExceeded);
+            }
+            Ok(Self::init(total_supply, name, symbol, decimals, cap))
+        }
+
+        /// Creates the coin and gives the whole `total_supply` to the caller.
+        fn init(
+            total_supply: Balance,
+            name: String,
+            symbol: String,
+            decimals: u8,
+            cap: Option<Balance>,
+        ) -> Self {
             let mut balances = Mapping::new();
             balances.insert(Self::env().caller(), &total_supply);
 
@@ -47,25 +98,209 @@ mod kitty_coin {
             Self {
                 total_supply,
                 balances,
-                ..Default::default()
+                allowances: Mapping::new(),
+                owner: Self::env().caller(),
+                name,
+                symbol,
+                decimals,
+                cap,
+                nonces: Mapping::new(),
+                paused: false,
             }
         }
 
-        pub fn transfer_helper(
+        /// Creates `value` new coins for `to`. Only the owner can mint.
+        #[ink(message)]
+        pub fn mint(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            self.ensure_not_paused()?;
+            let total_supply = self.total_supply.checked_add(value).ok_or(Error::Overflow)?;
+            if self.cap.is_some_and(|cap| total_supply > cap) {
+                return Err(Error::CapExceeded);
+            }
+            let balance_to = self.balance_of(to).checked_add(value).ok_or(Error::Overflow)?;
+
+            self.total_supply = total_supply;
+            self.balances.insert(to, &balance_to);
+
+            self.env().emit_event(Transfer {
+                from: None,
+                to: Some(to),
+                value,
+            });
+
+            Ok(())
+        }
+
+        /// Returns `true` if transfers, approvals, minting and burning are stopped.
+        #[ink(message)]
+        pub fn paused(&self) -> bool {
+            self.paused
+        }
+
+        /// Stops or resumes transfers, approvals, minting and burning. Only the owner can
+        /// pause.
+        #[ink(message)]
+        pub fn set_paused(&mut self, paused: bool) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            self.paused = paused;
+            Ok(())
+        }
+
+        /// Returns the maximum total supply minting can reach.
+        #[ink(message)]
+        pub fn cap(&self) -> Option<Balance> {
+            self.cap
+        }
+
+        /// Destroys `value` coins of the caller.
+        #[ink(message)]
+        pub fn burn(&mut self, value: Balance) -> Result<()> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let balance = self.balance_of(caller);
+            if value > balance {
+                return Err(Error::BalanceTooLow);
+            }
+
+            self.balances.insert(caller, &(balance - value));
+            self.total_supply -= value;
+
+            self.env().emit_event(Transfer {
+                from: Some(caller),
+                to: None,
+                value,
+            });
+
+            Ok(())
+        }
+
+        /// Returns the nonce the next permit of `owner` has to be signed for.
+        #[ink(message)]
+        pub fn nonce_of(&self, owner: AccountId) -> u64 {
+            self.nonces.get(owner).unwrap_or_default()
+        }
+
+        /// Returns the hash `owner` signs to permit `spender` to withdraw up to `value`
+        /// until `deadline`.
+        ///
+        /// It covers this contract's account and the owner's current nonce, so a permit
+        /// can't be used on another contract or twice.
+        #[ink(message)]
+        pub fn permit_hash(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+            deadline: Timestamp,
+        ) -> [u8; 32] {
+            let mut output = <Blake2x256 as HashOutput>::Type::default();
+            ink::env::hash_encoded::<Blake2x256, _>(
+                &(
+                    b"KittyCoin permit",
+                    self.env().account_id(),
+                    owner,
+                    spender,
+                    value,
+                    self.nonce_of(owner),
+                    deadline,
+                ),
+                &mut output,
+            );
+            output
+        }
+
+        /// Sets the allowance of `spender` from the `owner` account to `value` with the
+        /// owner's ECDSA signature over `permit_hash`, so anyone can submit it.
+        ///
+        /// The owner account has to be the Blake2x256 hash of the signing public key, as
+        /// for Substrate ECDSA accounts. Fails with `PermitExpired` after `deadline`.
+        #[ink(message)]
+        pub fn permit(
             &mut self,
-            from: &AccountId,
-            to: &AccountId,
+            owner: AccountId,
+            spender: AccountId,
             value: Balance,
+            deadline: Timestamp,
+            signature: [u8; 65],
         ) -> Result<()> {
-            let balance_from = self.balance_of(*from);
-            let balance_to = self.balance_of(*to);
+            self.ensure_not_paused()?;
+            if self.env().block_timestamp() > deadline {
+                return Err(Error::PermitExpired);
+            }
+
+            let message_hash = self.permit_hash(owner, spender, value, deadline);
+            let mut public_key = [0; 33];
+            ink::env::ecdsa_recover(&signature, &message_hash, &mut public_key)
+                .map_err(|_| Error::InvalidSignature)?;
+            let mut signer = <Blake2x256 as HashOutput>::Type::default();
+            ink::env::hash_bytes::<Blake2x256>(&public_key, &mut signer);
+            if AccountId::from(signer) != owner {
+                return Err(Error::InvalidSignature);
+            }
+
+            self.nonces.insert(owner, &(self.nonce_of(owner) + 1));
+            self.allowances.insert((owner, spender), &value);
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value,
+            });
 
-            if value > balance_from {
+            Ok(())
+        }
+
+        /// Transfers to several recipients from the caller's account at once.
+        /// Emits a `Transfer` event for every entry.
+        ///
+        /// Fails without transferring anything if the caller's balance doesn't cover the
+        /// total; any other failing transfer reverts the whole call.
+        #[ink(message)]
+        pub fn transfer_batch(&mut self, recipients: Vec<(AccountId, Balance)>) -> Result<()> {
+            let sender = self.env().caller();
+            let total = recipients
+                .iter()
+                .try_fold(0 as Balance, |total, (_, value)| total.checked_add(*value))
+                .ok_or(Error::Overflow)?;
+            if total > self.balance_of(sender) {
                 return Err(Error::BalanceTooLow);
             }
 
-            self.balances.insert(from, &(balance_from - value));
-            self.balances.insert(to, &(balance_to + value));
+            for (to, value) in recipients {
+                self.transfer_helper(&sender, &to, value)?;
+            }
+            Ok(())
+        }
+
+        /// Sets the allowance of several spenders from the caller's account at once.
+        /// Emits an `Approval` event for every entry.
+        #[ink(message)]
+        pub fn approve_many(&mut self, approvals: Vec<(AccountId, Balance)>) -> Result<()> {
+            for (spender, value) in approvals {
+                self.approve(spender, value)?;
+            }
+            Ok(())
+        }
+
+        pub fn transfer_helper(
+            &mut self,
+            from: &AccountId,
+            to: &AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            self.ensure_not_paused()?;
+            let balance_from = self
+                .balance_of(*from)
+                .checked_sub(value)
+                .ok_or(Error::BalanceTooLow)?;
+            let balance_to = self.balance_of(*to).checked_add(value).ok_or(Error::Overflow)?;
+
+            self.balances.insert(from, &balance_from);
+            self.balances.insert(to, &balance_to);
 
             self.env().emit_event(Transfer {
                 from: Some(*from),
@@ -75,6 +310,14 @@ mod kitty_coin {
 
             Ok(())
         }
+
+        /// Returns an error while transfers and approvals are stopped.
+        fn ensure_not_paused(&self) -> Result<()> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            Ok(())
+        }
     }
 
     impl TERC20 for KittyCoin {
@@ -98,10 +341,17 @@ mod kitty_coin {
             self.allowances.get(&(owner, spender)).unwrap_or_default()
         }
 
+        /// Returns the balance `spender` is still allowed to withdraw from the `owner` account.
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowances.get(&(owner, spender)).unwrap_or_default()
+        }
+
         /// Allows `spender` to withdraw from the caller's account multiple times, up to
         /// the `value` amount.
         #[ink(message)]
         fn approve(&mut self, spender: AccountId, value: Balance) -> Result<()> {
+            self.ensure_not_paused()?;
             let owner = self.env().caller();
             self.allowances.insert(&(owner, spender), &value);
 
@@ -114,6 +364,25 @@ mod kitty_coin {
             Ok(())
         }
 
+        /// Raises the allowance of `spender` from the caller's account by `delta`.
+        /// Fails with `Overflow` if the allowance would exceed `Balance::MAX`.
+        #[ink(message)]
+        fn increase_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            let allowance = self.allowances.get(&(owner, spender)).unwrap_or_default();
+            let value = allowance.checked_add(delta).ok_or(Error::Overflow)?;
+            self.approve(spender, value)
+        }
+
+        /// Lowers the allowance of `spender` from the caller's account by `delta`.
+        /// Lowering it by more than what is left sets it to zero instead of failing.
+        #[ink(message)]
+        fn decrease_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            let allowance = self.allowances.get(&(owner, spender)).unwrap_or_default();
+            self.approve(spender, allowance.saturating_sub(delta))
+        }
+
         /// Transfers the token from the caller to the given destination.
         #[ink(message)]
         fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()> {
@@ -125,6 +394,7 @@ mod kitty_coin {
         /// Caller has to hold an approval with enough fund to spend from the sender
         #[ink(message)]
         fn transfer_from(&mut self, from: AccountId, to: AccountId, value: Balance) -> Result<()> {
+            self.ensure_not_paused()?;
             let sender = self.env().caller();
             let allowance = self.allowances.get(&(from, sender)).unwrap_or_default();
 
@@ -139,6 +409,26 @@ mod kitty_coin {
         }
     }
 
+    impl TERC20Metadata for KittyCoin {
+        /// Returns the name of the token.
+        #[ink(message)]
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+
+        /// Returns the symbol of the token.
+        #[ink(message)]
+        fn symbol(&self) -> String {
+            self.symbol.clone()
+        }
+
+        /// Returns the number of decimals used to display token amounts.
+        #[ink(message)]
+        fn decimals(&self) -> u8 {
+            self.decimals
+        }
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -165,6 +455,27 @@ mod kitty_coin {
             }
         }
 
+        #[ink::test]
+        fn metadata_works() {
+            let kitty_coin = KittyCoin::with_metadata(
+                10_000,
+                String::from("Kitty Treats"),
+                String::from("TREAT"),
+                12,
+                None,
+            )
+            .expect("supply is uncapped");
+            assert_eq!(kitty_coin.name(), "Kitty Treats");
+            assert_eq!(kitty_coin.symbol(), "TREAT");
+            assert_eq!(kitty_coin.decimals(), 12);
+            assert_eq!(kitty_coin.total_supply(), 10_000);
+
+            let kitty_coin = KittyCoin::new(10_000);
+            assert_eq!(kitty_coin.name(), "KittyCoin");
+            assert_eq!(kitty_coin.symbol(), "KTC");
+            assert_eq!(kitty_coin.decimals(), 0);
+        }
+
         #[ink::test]
         fn transfer_should_work() {
             let mut kitty_coin = KittyCoin::new(10_000);
@@ -185,6 +496,279 @@ mod kitty_coin {
             assert!(res.is_err());
             assert_eq!(res, Err(Error::BalanceTooLow));
         }
+
+        #[ink::test]
+        fn transfer_overflow_should_fail() {
+            let mut kitty_coin = KittyCoin::new(10_000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            kitty_coin.balances.insert(accounts.bob, &(Balance::MAX - 1));
+
+            assert_eq!(kitty_coin.transfer(accounts.bob, 2), Err(Error::Overflow));
+            assert_eq!(kitty_coin.balance_of(accounts.alice), 10_000);
+            assert_eq!(kitty_coin.balance_of(accounts.bob), Balance::MAX - 1);
+            assert_eq!(kitty_coin.transfer(accounts.bob, 1), Ok(()));
+            assert_eq!(kitty_coin.balance_of(accounts.bob), Balance::MAX);
+        }
+
+        #[ink::test]
+        fn mint_works() {
+            let mut kitty_coin = KittyCoin::new(10_000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(kitty_coin.mint(accounts.bob, 500), Ok(()));
+            assert_eq!(kitty_coin.total_supply(), 10_500);
+            assert_eq!(kitty_coin.balance_of(accounts.bob), 500);
+            assert_eq!(kitty_coin.mint(accounts.bob, Balance::MAX), Err(Error::Overflow));
+            assert_eq!(kitty_coin.total_supply(), 10_500);
+        }
+
+        #[ink::test]
+        fn mint_up_to_cap_works() {
+            let mut kitty_coin = KittyCoin::with_metadata(
+                10_000,
+                String::from("KittyCoin"),
+                String::from("KTC"),
+                0,
+                Some(12_000),
+            )
+            .expect("supply is below the cap");
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(kitty_coin.cap(), Some(12_000));
+            assert_eq!(kitty_coin.mint(accounts.bob, 1_500), Ok(()));
+            assert_eq!(kitty_coin.mint(accounts.bob, 500), Ok(()));
+            assert_eq!(kitty_coin.total_supply(), 12_000);
+            // The cap is reached.
+            assert_eq!(kitty_coin.mint(accounts.bob, 1), Err(Error::CapExceeded));
+            assert_eq!(kitty_coin.balance_of(accounts.bob), 2_000);
+        }
+
+        #[ink::test]
+        fn initial_supply_above_cap_is_rejected() {
+            let kitty_coin = KittyCoin::with_metadata(
+                10_000,
+                String::from("KittyCoin"),
+                String::from("KTC"),
+                0,
+                Some(9_999),
+            );
+            assert_eq!(kitty_coin.err(), Some(Error::CapExceeded));
+        }
+
+        #[ink::test]
+        fn uncapped_mint_works() {
+            let mut kitty_coin = KittyCoin::new(10_000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(kitty_coin.cap(), None);
+            assert_eq!(kitty_coin.mint(accounts.bob, Balance::MAX - 10_000), Ok(()));
+            assert_eq!(kitty_coin.total_supply(), Balance::MAX);
+        }
+
+        #[ink::test]
+        fn non_owner_mint_should_fail() {
+            let mut kitty_coin = KittyCoin::new(10_000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(kitty_coin.mint(accounts.bob, 500), Err(Error::NotOwner));
+            assert_eq!(kitty_coin.total_supply(), 10_000);
+        }
+
+        #[ink::test]
+        fn burn_works() {
+            let mut kitty_coin = KittyCoin::new(10_000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(kitty_coin.burn(400), Ok(()));
+            assert_eq!(kitty_coin.total_supply(), 9_600);
+            assert_eq!(kitty_coin.balance_of(accounts.alice), 9_600);
+            assert_eq!(kitty_coin.burn(10_000), Err(Error::BalanceTooLow));
+        }
+
+        #[ink::test]
+        fn allowance_works() {
+            let mut kitty_coin = KittyCoin::new(10_000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(kitty_coin.approve(accounts.bob, 30), Ok(()));
+
+            // Charlie reads the allowance Alice granted Bob.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(kitty_coin.allowance(accounts.alice, accounts.bob), 30);
+            assert_eq!(kitty_coin.allowance(accounts.bob, accounts.alice), 0);
+            assert_eq!(kitty_coin.allowances_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn increase_allowance_works() {
+            let mut kitty_coin = KittyCoin::new(10_000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(kitty_coin.increase_allowance(accounts.bob, 10), Ok(()));
+            assert_eq!(kitty_coin.increase_allowance(accounts.bob, 5), Ok(()));
+            assert_eq!(kitty_coin.allowances_of(accounts.bob), 15);
+            assert_eq!(
+                kitty_coin.increase_allowance(accounts.bob, Balance::MAX),
+                Err(Error::Overflow)
+            );
+            assert_eq!(kitty_coin.allowances_of(accounts.bob), 15);
+        }
+
+        #[ink::test]
+        fn decrease_allowance_works() {
+            let mut kitty_coin = KittyCoin::new(10_000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(kitty_coin.approve(accounts.bob, 15), Ok(()));
+            assert_eq!(kitty_coin.decrease_allowance(accounts.bob, 5), Ok(()));
+            assert_eq!(kitty_coin.allowances_of(accounts.bob), 10);
+            // Decreasing past zero leaves no allowance.
+            assert_eq!(kitty_coin.decrease_allowance(accounts.bob, 20), Ok(()));
+            assert_eq!(kitty_coin.allowances_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn transfer_batch_works() {
+            let mut kitty_coin = KittyCoin::new(10_000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let res = kitty_coin.transfer_batch(vec![
+                (accounts.bob, 10),
+                (accounts.charlie, 20),
+                (accounts.django, 30),
+            ]);
+            assert!(res.is_ok());
+            assert_eq!(kitty_coin.balance_of(accounts.alice), 10_000 - 60);
+            assert_eq!(kitty_coin.balance_of(accounts.bob), 10);
+            assert_eq!(kitty_coin.balance_of(accounts.charlie), 20);
+            assert_eq!(kitty_coin.balance_of(accounts.django), 30);
+
+            // One Transfer event per entry after the mint Transfer event.
+            let emitted_events = ink::env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 4);
+        }
+
+        #[ink::test]
+        fn transfer_batch_over_balance_should_fail() {
+            let mut kitty_coin = KittyCoin::new(10_000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let res = kitty_coin.transfer_batch(vec![(accounts.bob, 6_000), (accounts.charlie, 6_000)]);
+            assert_eq!(res, Err(Error::BalanceTooLow));
+            assert_eq!(kitty_coin.balance_of(accounts.alice), 10_000);
+            assert_eq!(kitty_coin.balance_of(accounts.bob), 0);
+            assert_eq!(
+                kitty_coin.transfer_batch(vec![(accounts.bob, Balance::MAX), (accounts.charlie, 1)]),
+                Err(Error::Overflow)
+            );
+        }
+
+        /// Signs `message_hash` with `secret_key` as expected by `permit`.
+        fn sign(secret_key: &secp256k1::SecretKey, message_hash: [u8; 32]) -> [u8; 65] {
+            let message = secp256k1::Message::from_slice(&message_hash).unwrap();
+            let (recovery_id, compact) = secp256k1::SECP256K1
+                .sign_ecdsa_recoverable(&message, secret_key)
+                .serialize_compact();
+            let mut signature = [0; 65];
+            signature[..64].copy_from_slice(&compact);
+            signature[64] = recovery_id.to_i32() as u8;
+            signature
+        }
+
+        /// Returns the account of an ECDSA `secret_key`.
+        fn ecdsa_account(secret_key: &secp256k1::SecretKey) -> AccountId {
+            let public_key = secret_key.public_key(secp256k1::SECP256K1).serialize();
+            let mut account = <Blake2x256 as HashOutput>::Type::default();
+            ink::env::hash_bytes::<Blake2x256>(&public_key, &mut account);
+            AccountId::from(account)
+        }
+
+        #[ink::test]
+        fn permit_works() {
+            let mut kitty_coin = KittyCoin::new(10_000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let secret_key = secp256k1::SecretKey::from_slice(&[0x42; 32]).unwrap();
+            let owner = ecdsa_account(&secret_key);
+
+            let signature = sign(&secret_key, kitty_coin.permit_hash(owner, accounts.bob, 50, 1_000));
+            // Charlie submits the permit for the owner.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(kitty_coin.permit(owner, accounts.bob, 50, 1_000, signature), Ok(()));
+            assert_eq!(kitty_coin.allowance(owner, accounts.bob), 50);
+            assert_eq!(kitty_coin.nonce_of(owner), 1);
+
+            // The same permit can't be replayed.
+            assert_eq!(
+                kitty_coin.permit(owner, accounts.bob, 50, 1_000, signature),
+                Err(Error::InvalidSignature)
+            );
+            // The signature doesn't cover another spender.
+            let signature = sign(&secret_key, kitty_coin.permit_hash(owner, accounts.bob, 70, 1_000));
+            assert_eq!(
+                kitty_coin.permit(owner, accounts.django, 70, 1_000, signature),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(kitty_coin.allowance(owner, accounts.django), 0);
+        }
+
+        #[ink::test]
+        fn expired_permit_should_fail() {
+            let mut kitty_coin = KittyCoin::new(10_000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let secret_key = secp256k1::SecretKey::from_slice(&[0x42; 32]).unwrap();
+            let owner = ecdsa_account(&secret_key);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(2_000);
+            let signature = sign(&secret_key, kitty_coin.permit_hash(owner, accounts.bob, 50, 1_000));
+            assert_eq!(
+                kitty_coin.permit(owner, accounts.bob, 50, 1_000, signature),
+                Err(Error::PermitExpired)
+            );
+            assert_eq!(kitty_coin.allowance(owner, accounts.bob), 0);
+            assert_eq!(kitty_coin.nonce_of(owner), 0);
+        }
+
+        #[ink::test]
+        fn paused_transfers_should_fail() {
+            let mut kitty_coin = KittyCoin::new(10_000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(kitty_coin.approve(accounts.bob, 100), Ok(()));
+            assert_eq!(kitty_coin.set_paused(true), Ok(()));
+            assert!(kitty_coin.paused());
+
+            assert_eq!(kitty_coin.transfer(accounts.bob, 12), Err(Error::Paused));
+            assert_eq!(kitty_coin.approve(accounts.charlie, 10), Err(Error::Paused));
+            assert_eq!(kitty_coin.mint(accounts.bob, 10), Err(Error::Paused));
+            assert_eq!(kitty_coin.burn(10), Err(Error::Paused));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                kitty_coin.transfer_from(accounts.alice, accounts.bob, 12),
+                Err(Error::Paused)
+            );
+            // Balances stay readable.
+            assert_eq!(kitty_coin.balance_of(accounts.alice), 10_000);
+            assert_eq!(kitty_coin.total_supply(), 10_000);
+            assert_eq!(kitty_coin.allowance(accounts.alice, accounts.bob), 100);
+
+            // Only the owner can resume transfers.
+            assert_eq!(kitty_coin.set_paused(false), Err(Error::NotOwner));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(kitty_coin.set_paused(false), Ok(()));
+            assert_eq!(kitty_coin.transfer(accounts.bob, 12), Ok(()));
+            assert_eq!(kitty_coin.balance_of(accounts.bob), 12);
+            assert_eq!(kitty_coin.burn(10), Ok(()));
+            assert_eq!(kitty_coin.total_supply(), 9_990);
+        }
+
+        #[ink::test]
+        fn approve_many_works() {
+            let mut kitty_coin = KittyCoin::new(10_000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let res = kitty_coin.approve_many(vec![
+                (accounts.bob, 10),
+                (accounts.charlie, 20),
+                (accounts.django, 30),
+            ]);
+            assert!(res.is_ok());
+            assert_eq!(kitty_coin.allowances_of(accounts.bob), 10);
+            assert_eq!(kitty_coin.allowances_of(accounts.charlie), 20);
+            assert_eq!(kitty_coin.allowances_of(accounts.django), 30);
+
+            // One Approval event per entry after the mint Transfer event.
+            let emitted_events = ink::env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 4);
+        }
     }
 
     #[cfg(all(test, feature = "e2e-tests"))]
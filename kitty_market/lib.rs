@@ -2,10 +2,19 @@
 
 #[ink::contract]
 mod kitty_market {
-    use ink::{prelude::vec::Vec, storage::Mapping};
-    use trait_erc721::{TERC721, KittyId};
+    use ink::{codegen::TraitCallBuilder, prelude::vec::Vec, storage::Mapping};
+    use trait_erc721::{
+        TKittiesMarket, TKittySaleHook, TKittyTransferHook, TERC2981, TERC721, KittyId,
+    };
     use trait_erc20::{TERC20};
 
+    /// Maximum number of listings scanned by price statistics, bounding their gas cost.
+    const MAX_PRICE_SCAN: usize = 100;
+    /// Maximum number of entries returned by a paged listing query.
+    const MAX_PAGE_SIZE: u32 = 100;
+    /// Maximum length in bytes of a listing description.
+    const MAX_DESCRIPTION_LEN: usize = 256;
+
     #[ink(storage)]
     pub struct KittyMarket {
         kitties_contract_account: AccountId,
@@ -18,6 +27,147 @@ mod kitty_market {
         /// A list of kitties needs adoption
         kitties_for_adoption: Vec<KittyId>,
         minted_count: u32,
+        /// Market owner allowed to configure the market.
+        owner: AccountId,
+        /// Whether listed kitties get locked on the kitties contract while listed.
+        listed_lock: bool,
+        /// Number of blocks over which sale proceeds vest, zero pays sellers instantly.
+        vesting_blocks: BlockNumber,
+        /// A mapping from seller to its vesting proceeds and the block vesting started.
+        vesting_proceeds: Mapping<AccountId, (u128, BlockNumber)>,
+        /// A mapping from seller to the part of its vesting proceeds already claimed.
+        claimed_proceeds: Mapping<AccountId, u128>,
+        /// A mapping from seller to proceeds that finished vesting before a new sale
+        /// restarted its vesting, claimable right away.
+        released_proceeds: Mapping<AccountId, u128>,
+        /// Number of blocks an adopted kitty can't be listed for adoption again, zero disables it.
+        adopt_cooldown_blocks: BlockNumber,
+        /// A mapping from adopted kitty to the block it was adopted at.
+        adopted_at: Mapping<KittyId, BlockNumber>,
+        /// Minimum kitty coin balance required to list a kitty, zero disables the check.
+        min_lister_balance: u128,
+        /// Commission on sales in basis points.
+        fee_bps: u16,
+        /// Account receiving the commission on sales.
+        fee_recipient: AccountId,
+        /// A mapping from kitty listed for sale to the seller's description of it.
+        listing_descriptions: Mapping<KittyId, Vec<u8>>,
+        /// A mapping from kitty listed in a dutch auction to the auction.
+        dutch_auctions: Mapping<KittyId, DutchAuction>,
+        /// A mapping from kitty listed in an english auction to the auction.
+        auctions: Mapping<KittyId, EnglishAuction>,
+        /// A mapping from kitty and bidder to the amount the market holds for the offer.
+        offers: Mapping<(KittyId, AccountId), u128>,
+        /// A mapping from kitty to the number of standing offers for it.
+        offer_count: Mapping<KittyId, u32>,
+        /// A mapping from index to the kitty and bidder of a standing offer.
+        offer_keys: Mapping<u32, (KittyId, AccountId)>,
+        /// A mapping from kitty and bidder of a standing offer to its index in `offer_keys`.
+        offer_key_index: Mapping<(KittyId, AccountId), u32>,
+        /// Number of standing offers.
+        offer_key_count: u32,
+        /// Contract notified about every sale.
+        sale_hook: Option<AccountId>,
+        /// A mapping from kitty listed for sale to the last block it can be bought at.
+        sale_expiry: Mapping<KittyId, BlockNumber>,
+        /// Whether kitties opted in to abandonment can be burned after long inactivity.
+        allow_abandonment: bool,
+        /// Number of blocks without listing activity after which an opted in kitty is
+        /// abandoned.
+        abandonment_blocks: BlockNumber,
+        /// A mapping from kitty listed for sale and opted in to abandonment to the block
+        /// of its last listing activity.
+        abandonable_since: Mapping<KittyId, BlockNumber>,
+        /// Whether a message moving coins or kitties is running.
+        locked: bool,
+        /// A mapping from kitty listed for sale to the seller that listed it.
+        sale_sellers: Mapping<KittyId, AccountId>,
+        /// A mapping from seller to the kitties it listed for sale.
+        seller_listings: Mapping<AccountId, Vec<KittyId>>,
+        /// A mapping from kitty listed for adoption to the fee its adopter pays the owner.
+        adoption_fees: Mapping<KittyId, u128>,
+        /// Kitty coin the market holds for auction bids, offers and vesting proceeds.
+        escrowed: u128,
+        /// A mapping from seller to the number of its kitties bought through `buy`.
+        sales_by_seller: Mapping<AccountId, u32>,
+        /// A mapping from lister to the number of its kitties adopted.
+        adoptions_by_lister: Mapping<AccountId, u32>,
+        /// Lowest price a kitty can be listed for sale at.
+        min_price: u128,
+        /// Highest price a kitty can be listed for sale at, unbounded if `None`.
+        max_price: Option<u128>,
+    }
+
+    /// An auction whose price declines linearly from `start_price` to `end_price` over
+    /// `duration_blocks` blocks from `start_block`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct DutchAuction {
+        pub start_price: u128,
+        pub end_price: u128,
+        pub start_block: BlockNumber,
+        pub duration_blocks: BlockNumber,
+    }
+
+    /// An auction taking increasing bids of at least `reserve_price` until `end_block`.
+    /// The market holds the highest bid until the auction is settled.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct EnglishAuction {
+        pub seller: AccountId,
+        pub reserve_price: u128,
+        pub end_block: BlockNumber,
+        pub highest_bidder: Option<AccountId>,
+        pub highest_bid: u128,
+    }
+
+    /// Whether the owner and the market approval of a kitty still support selling it.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub struct ListingHealth {
+        /// The kitty still has an owner.
+        pub owner_ok: bool,
+        /// The market is still approved to transfer the kitty.
+        pub approval_ok: bool,
+    }
+
+    /// Market activity a kitty is involved in.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub struct Involvement {
+        /// The kitty is listed for sale at a fixed price.
+        pub for_sale: bool,
+        /// The kitty is waiting to be adopted.
+        pub for_adoption: bool,
+        /// Someone offers to buy the kitty.
+        pub has_offers: bool,
+        /// The kitty is listed in a dutch or english auction.
+        pub in_auction: bool,
+    }
+
+    /// Parts of a sale price paid to others than the seller.
+    struct Deductions {
+        fee: u128,
+        royalty_receiver: AccountId,
+        royalty: u128,
+    }
+
+    /// Event emitted when the market is created, announcing the contracts it works with,
+    /// its commission and its sale price bounds.
+    #[ink(event)]
+    pub struct MarketInitialized {
+        kitties: AccountId,
+        kitty_coin: AccountId,
+        fee_bps: u16,
+        fee_recipient: AccountId,
+        min_price: u128,
+        max_price: Option<u128>,
     }
 
     #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
@@ -47,6 +197,46 @@ mod kitty_market {
         ListAdoptNotApproved,
         /// Kitties contract account failed to gain the permission to transfer kitty to future buyer
         ListSaleNotApproved,
+        /// Only the market owner can configure the market
+        NotMarketOwner,
+        /// Failed to lock or unlock a listed kitty on the kitties contract
+        MarketLockFail,
+        /// Kitty was adopted too recently to be listed for adoption again
+        AdoptCooldown,
+        /// Lister holds less kitty coin than required to list a kitty
+        InsufficientListerBalance,
+        /// Listing description is longer than allowed
+        DescriptionTooLong,
+        /// Dutch auction end price is above its start price, or an auction ends in the past
+        InvalidAuction,
+        /// Bid is below the reserve price or does not exceed the highest bid
+        BidTooLow,
+        /// Auction does not take bids anymore
+        AuctionEnded,
+        /// Auction can't be settled before its end block
+        AuctionNotEnded,
+        /// Bidder has no offer for the kitty
+        NoOffer,
+        /// Sale listing has expired
+        ListingExpired,
+        /// Message reentered the market while it was moving coins or kitties
+        Reentrancy,
+        /// Asset is escrowed or not held by the market
+        NotRescuable,
+        /// Failed to mint a kitty on the kitties contract
+        MintFail,
+        /// Sale price is below the market minimum
+        PriceTooLow,
+        /// Sale price is above the market maximum
+        PriceTooHigh,
+        /// Buyer did not approve the market for the full price
+        InsufficientAllowance,
+        /// Buyer holds less kitty coin than the price
+        InsufficientBalance,
+        /// Market is no longer approved to transfer the kitty
+        TransferNotApproved,
+        /// Kitty does not exist on the kitties contract
+        KittyNotFound,
     }
 
     pub type Result<T> = core::result::Result<T, Error>;
@@ -65,6 +255,15 @@ mod kitty_market {
         adopter: AccountId,
         #[ink(topic)]
         kitty_id: KittyId,
+        fee: u128,
+    }
+
+    #[ink(event)]
+    pub struct UnlistedForAdoption {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        kitty_id: KittyId,
     }
 
     #[ink(event)]
@@ -76,6 +275,81 @@ mod kitty_market {
         price: u128,
     }
 
+    #[ink(event)]
+    pub struct ListedForDutchAuction {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        kitty_id: KittyId,
+        start_price: u128,
+        end_price: u128,
+        duration_blocks: BlockNumber,
+    }
+
+    #[ink(event)]
+    pub struct ListedForAuction {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        kitty_id: KittyId,
+        reserve_price: u128,
+        end_block: BlockNumber,
+    }
+
+    #[ink(event)]
+    pub struct BidPlaced {
+        #[ink(topic)]
+        bidder: AccountId,
+        #[ink(topic)]
+        kitty_id: KittyId,
+        amount: u128,
+    }
+
+    #[ink(event)]
+    pub struct OfferMade {
+        #[ink(topic)]
+        bidder: AccountId,
+        #[ink(topic)]
+        kitty_id: KittyId,
+        amount: u128,
+    }
+
+    #[ink(event)]
+    pub struct OfferCancelled {
+        #[ink(topic)]
+        bidder: AccountId,
+        #[ink(topic)]
+        kitty_id: KittyId,
+    }
+
+    #[ink(event)]
+    pub struct PriceUpdated {
+        #[ink(topic)]
+        kitty_id: KittyId,
+        old_price: u128,
+        new_price: u128,
+    }
+
+    #[ink(event)]
+    pub struct UnlistedForSale {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        kitty_id: KittyId,
+    }
+
+    #[ink(event)]
+    pub struct ListingReaped {
+        #[ink(topic)]
+        kitty_id: KittyId,
+    }
+
+    #[ink(event)]
+    pub struct KittyAbandoned {
+        #[ink(topic)]
+        kitty_id: KittyId,
+    }
+
     #[ink(event)]
     pub struct Sold {
         #[ink(topic)]
@@ -85,11 +359,37 @@ mod kitty_market {
         #[ink(topic)]
         kitty_id: KittyId,
         price: u128,
+        fee: u128,
+        royalty_receiver: AccountId,
+        royalty: u128,
     }
 
     impl KittyMarket {
+        /// Creates a market taking a commission of `fee_bps` basis points, capped at 10000,
+        /// on every sale for `fee_recipient`
+        ///
+        /// Kitties can only be listed for sale at prices from `min_price` to `max_price`. A
+        /// `max_price` of `None` or zero leaves the price unbounded.
         #[ink(constructor)]
-        pub fn new(kitties: AccountId, kitty_coin: AccountId) -> Self {
+        pub fn new(
+            kitties: AccountId,
+            kitty_coin: AccountId,
+            fee_bps: u16,
+            fee_recipient: AccountId,
+            min_price: u128,
+            max_price: Option<u128>,
+        ) -> Self {
+            let fee_bps = fee_bps.min(10_000);
+            let max_price = max_price.filter(|max_price| *max_price > 0);
+            Self::env().emit_event(MarketInitialized {
+                kitties,
+                kitty_coin,
+                fee_bps,
+                fee_recipient,
+                min_price,
+                max_price,
+            });
+
             Self {
                 kitties_contract_account: kitties.clone(),
                 kitty_coin: kitty_coin.into(),
@@ -97,8 +397,253 @@ mod kitty_market {
                 kitties_for_sale: Mapping::new(),
                 kitty_ids_for_sale: Vec::new(),
                 kitties_for_adoption: Vec::new(),
-                minted_count: 0,                
+                minted_count: 0,
+                owner: Self::env().caller(),
+                listed_lock: false,
+                vesting_blocks: 0,
+                vesting_proceeds: Mapping::new(),
+                claimed_proceeds: Mapping::new(),
+                released_proceeds: Mapping::new(),
+                adopt_cooldown_blocks: 0,
+                adopted_at: Mapping::new(),
+                min_lister_balance: 0,
+                fee_bps,
+                fee_recipient,
+                listing_descriptions: Mapping::new(),
+                dutch_auctions: Mapping::new(),
+                auctions: Mapping::new(),
+                offers: Mapping::new(),
+                offer_count: Mapping::new(),
+                offer_keys: Mapping::new(),
+                offer_key_index: Mapping::new(),
+                offer_key_count: 0,
+                sale_hook: None,
+                sale_expiry: Mapping::new(),
+                allow_abandonment: false,
+                abandonment_blocks: 0,
+                abandonable_since: Mapping::new(),
+                locked: false,
+                sale_sellers: Mapping::new(),
+                seller_listings: Mapping::new(),
+                adoption_fees: Mapping::new(),
+                escrowed: 0,
+                sales_by_seller: Mapping::new(),
+                adoptions_by_lister: Mapping::new(),
+                min_price,
+                max_price,
+            }
+        }
+
+        /// Returns the contract notified about every sale
+        #[ink(message)]
+        pub fn sale_hook(&self) -> Option<AccountId> {
+            self.sale_hook
+        }
+
+        /// Sets the contract notified about every sale, `None` stops notifications
+        #[ink(message)]
+        pub fn set_sale_hook(&mut self, sale_hook: Option<AccountId>) -> Result<()> {
+            self.ensure_owner()?;
+            self.sale_hook = sale_hook;
+            Ok(())
+        }
+
+        /// Returns whether opted in kitties can be burned after long inactivity and the
+        /// number of inactive blocks after which they are
+        #[ink(message)]
+        pub fn abandonment(&self) -> (bool, BlockNumber) {
+            (self.allow_abandonment, self.abandonment_blocks)
+        }
+
+        /// Allows or forbids burning opted in kitties whose listing has been inactive for
+        /// more than `abandonment_blocks`
+        #[ink(message)]
+        pub fn set_abandonment(
+            &mut self,
+            allow_abandonment: bool,
+            abandonment_blocks: BlockNumber,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            self.allow_abandonment = allow_abandonment;
+            self.abandonment_blocks = abandonment_blocks;
+            Ok(())
+        }
+
+        /// Returns the kitties contract traded on this market
+        #[ink(message)]
+        pub fn kitties_contract(&self) -> AccountId {
+            self.kitties_contract_account
+        }
+
+        /// Returns the kitty coin contract used for payments
+        #[ink(message)]
+        pub fn coin_contract(&self) -> AccountId {
+            ink::ToAccountId::to_account_id(&self.kitty_coin)
+        }
+
+        /// Returns the commission on sales in basis points and its recipient
+        #[ink(message)]
+        pub fn fee(&self) -> (u16, AccountId) {
+            (self.fee_bps, self.fee_recipient)
+        }
+
+        /// Returns the lowest and highest price a kitty can be listed for sale at, the
+        /// highest being unbounded if `None`
+        #[ink(message)]
+        pub fn price_bounds(&self) -> (u128, Option<u128>) {
+            (self.min_price, self.max_price)
+        }
+
+        /// Returns the minimum kitty coin balance required to list a kitty
+        #[ink(message)]
+        pub fn min_lister_balance(&self) -> u128 {
+            self.min_lister_balance
+        }
+
+        /// Sets the minimum kitty coin balance required to list a kitty, zero disables the check
+        #[ink(message)]
+        pub fn set_min_lister_balance(&mut self, min_lister_balance: u128) -> Result<()> {
+            self.ensure_owner()?;
+            self.min_lister_balance = min_lister_balance;
+            Ok(())
+        }
+
+        /// Returns the number of blocks an adopted kitty can't be listed for adoption again
+        #[ink(message)]
+        pub fn adopt_cooldown_blocks(&self) -> BlockNumber {
+            self.adopt_cooldown_blocks
+        }
+
+        /// Sets the number of blocks an adopted kitty can't be listed for adoption again,
+        /// zero disables the cooldown
+        #[ink(message)]
+        pub fn set_adopt_cooldown_blocks(&mut self, adopt_cooldown_blocks: BlockNumber) -> Result<()> {
+            self.ensure_owner()?;
+            self.adopt_cooldown_blocks = adopt_cooldown_blocks;
+            Ok(())
+        }
+
+        /// Returns the number of blocks over which sale proceeds vest
+        #[ink(message)]
+        pub fn vesting_blocks(&self) -> BlockNumber {
+            self.vesting_blocks
+        }
+
+        /// Sets the number of blocks over which sale proceeds vest, zero pays sellers instantly
+        #[ink(message)]
+        pub fn set_vesting_blocks(&mut self, vesting_blocks: BlockNumber) -> Result<()> {
+            self.ensure_owner()?;
+            self.vesting_blocks = vesting_blocks;
+            Ok(())
+        }
+
+        /// Returns the vested sale proceeds `seller` can claim right now
+        #[ink(message)]
+        pub fn releasable_proceeds(&self, seller: AccountId) -> u128 {
+            let released = self.released_proceeds.get(seller).unwrap_or(0);
+            let claimed = self.claimed_proceeds.get(seller).unwrap_or(0);
+            released + self.vested_proceeds(seller).saturating_sub(claimed)
+        }
+
+        /// Pays out the caller's vested sale proceeds and returns the amount paid
+        #[ink(message)]
+        pub fn claim_proceeds(&mut self) -> Result<u128> {
+            self.non_reentrant(|market| market.claim_proceeds_inner())
+        }
+
+        fn claim_proceeds_inner(&mut self) -> Result<u128> {
+            let seller = self.env().caller();
+            let releasable = self.releasable_proceeds(seller);
+            if releasable == 0 {
+                return Ok(0);
+            }
+
+            let claim_result = self.kitty_coin.transfer(seller, releasable);
+            if claim_result.is_err() {
+                return Err(Error::CoinTransferFail);
+            }
+            self.escrowed = self.escrowed.saturating_sub(releasable);
+
+            self.released_proceeds.remove(seller);
+            if let Some((amount, _)) = self.vesting_proceeds.get(seller) {
+                let claimed = self.vested_proceeds(seller);
+                if claimed >= amount {
+                    self.vesting_proceeds.remove(seller);
+                    self.claimed_proceeds.remove(seller);
+                } else {
+                    self.claimed_proceeds.insert(seller, &claimed);
+                }
+            }
+
+            Ok(releasable)
+        }
+
+        /// Returns the kitty coin the market holds for auction bids, offers and vesting
+        /// proceeds
+        #[ink(message)]
+        pub fn escrowed(&self) -> u128 {
+            self.escrowed
+        }
+
+        /// Sends kitty `kitty_id`, which ended up owned by the market, to `to`
+        ///
+        /// Only the market owner can call this. The market never holds listed kitties,
+        /// so any kitty it owns was sent to it by mistake.
+        #[ink(message)]
+        pub fn rescue_kitty(&mut self, kitty_id: KittyId, to: AccountId) -> Result<()> {
+            self.non_reentrant(|market| market.rescue_kitty_inner(kitty_id, to))
+        }
+
+        fn rescue_kitty_inner(&mut self, kitty_id: KittyId, to: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            if self.kitties.owner_of(kitty_id) != Some(self.env().account_id()) {
+                return Err(Error::NotRescuable);
+            }
+
+            let transfer_result = self.kitties.transfer(to, kitty_id);
+            if transfer_result.is_err() {
+                return Err(Error::OwnershipTransferFail);
+            }
+
+            Ok(())
+        }
+
+        /// Sends `amount` of the kitty coin the market holds beyond its escrow to `to`
+        ///
+        /// Only the market owner can call this. Escrowed bids, offers and vesting proceeds
+        /// can't be rescued.
+        #[ink(message)]
+        pub fn rescue_coin(&mut self, amount: u128, to: AccountId) -> Result<()> {
+            self.non_reentrant(|market| market.rescue_coin_inner(amount, to))
+        }
+
+        fn rescue_coin_inner(&mut self, amount: u128, to: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            let balance = self.kitty_coin.balance_of(self.env().account_id());
+            if amount > balance.saturating_sub(self.escrowed) {
+                return Err(Error::NotRescuable);
+            }
+
+            let transfer_result = self.kitty_coin.transfer(to, amount);
+            if transfer_result.is_err() {
+                return Err(Error::CoinTransferFail);
             }
+
+            Ok(())
+        }
+
+        /// Returns whether listed kitties get locked on the kitties contract
+        #[ink(message)]
+        pub fn listed_lock(&self) -> bool {
+            self.listed_lock
+        }
+
+        /// Enables or disables locking listed kitties on the kitties contract
+        #[ink(message)]
+        pub fn set_listed_lock(&mut self, enabled: bool) -> Result<()> {
+            self.ensure_owner()?;
+            self.listed_lock = enabled;
+            Ok(())
         }
 
         /// Returns list of kitties waiting to be adopted
@@ -113,29 +658,178 @@ mod kitty_market {
             self.kitty_ids_for_sale.iter().map(|&id| (id, self.kitties_for_sale.get(&id).unwrap())).collect()
         }
 
-        /// List a kitty for adoption
+        /// Returns the price of a kitty listed for sale, or `None` if it isn't listed
+        #[ink(message)]
+        pub fn sale_price_of(&self, kitty_id: KittyId) -> Option<u128> {
+            self.kitties_for_sale.get(kitty_id)
+        }
+
+        /// Returns `true` if kitty `kitty_id` is listed for sale at a fixed price
+        #[ink(message)]
+        pub fn is_for_sale(&self, kitty_id: KittyId) -> bool {
+            self.kitties_for_sale.contains(kitty_id)
+        }
+
+        /// Returns `true` if kitty `kitty_id` is waiting to be adopted
+        #[ink(message)]
+        pub fn is_for_adoption(&self, kitty_id: KittyId) -> bool {
+            self.kitties_for_adoption.contains(&kitty_id)
+        }
+
+        /// Returns every kind of market activity kitty `kitty_id` is involved in
+        #[ink(message)]
+        pub fn market_involvement(&self, kitty_id: KittyId) -> Involvement {
+            Involvement {
+                for_sale: self.kitties_for_sale.contains(kitty_id),
+                for_adoption: self.kitties_for_adoption.contains(&kitty_id),
+                has_offers: self.offer_count.contains(kitty_id),
+                in_auction: self.dutch_auctions.contains(kitty_id)
+                    || self.auctions.contains(kitty_id),
+            }
+        }
+
+        /// Returns the kitties `seller` listed for sale with their prices.
+        ///
+        /// Only the listings of `seller` are read, so the cost grows with the number of
+        /// kitties it has for sale.
+        #[ink(message)]
+        pub fn sale_listings_of(&self, seller: AccountId) -> Vec<(KittyId, u128)> {
+            self.seller_listings
+                .get(seller)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|id| (id, self.kitties_for_sale.get(id).unwrap_or_default()))
+                .collect()
+        }
+
+        /// Returns the number of kitties of `seller` bought at their listed price
+        #[ink(message)]
+        pub fn sales_by_seller(&self, seller: AccountId) -> u32 {
+            self.sales_by_seller.get(seller).unwrap_or(0)
+        }
+
+        /// Returns the number of kitties `lister` listed for adoption that got adopted
+        #[ink(message)]
+        pub fn adoptions_by_lister(&self, lister: AccountId) -> u32 {
+            self.adoptions_by_lister.get(lister).unwrap_or(0)
+        }
+
+        /// Returns up to `limit` kitties for sale starting at index `start`.
+        ///
+        /// `limit` is clamped to `MAX_PAGE_SIZE`.
+        #[ink(message)]
+        pub fn kitties_for_sale_paged(&self, start: u32, limit: u32) -> Vec<(KittyId, u128)> {
+            self.kitty_ids_for_sale
+                .iter()
+                .skip(start as usize)
+                .take(limit.min(MAX_PAGE_SIZE) as usize)
+                .map(|&id| (id, self.kitties_for_sale.get(id).unwrap_or_default()))
+                .collect()
+        }
+
+        /// Returns up to `limit` kitties waiting to be adopted starting at index `start`.
+        ///
+        /// `limit` is clamped to `MAX_PAGE_SIZE`.
+        #[ink(message)]
+        pub fn adoption_list_paged(&self, start: u32, limit: u32) -> Vec<KittyId> {
+            self.kitties_for_adoption
+                .iter()
+                .skip(start as usize)
+                .take(limit.min(MAX_PAGE_SIZE) as usize)
+                .copied()
+                .collect()
+        }
+
+        /// Returns the number of kitties for sale.
+        #[ink(message)]
+        pub fn sale_count(&self) -> u32 {
+            self.kitty_ids_for_sale.len() as u32
+        }
+
+        /// Returns the number of kitties waiting to be adopted.
+        #[ink(message)]
+        pub fn adoption_count(&self) -> u32 {
+            self.kitties_for_adoption.len() as u32
+        }
+
+        /// Returns the lowest fixed sale price, the highest standing offer and how far the
+        /// offer is below the price, or `None` if there are no listings or no offers.
+        ///
+        /// The spread is zero if the best offer reaches the floor price. Only the first
+        /// `MAX_PRICE_SCAN` listings and offers are considered to bound the gas cost.
         #[ink(message)]
-        pub fn list_for_adoption(&mut self, kitty_id: KittyId) -> Result<()> {
+        pub fn bid_ask_spread(&self) -> Option<(u128, u128, u128)> {
+            let floor_price = self
+                .kitty_ids_for_sale
+                .iter()
+                .take(MAX_PRICE_SCAN)
+                .filter_map(|&id| self.kitties_for_sale.get(id))
+                .min()?;
+            let best_offer = (0..self.offer_key_count)
+                .take(MAX_PRICE_SCAN)
+                .filter_map(|index| self.offer_keys.get(index))
+                .filter_map(|key| self.offers.get(key))
+                .max()?;
+            Some((floor_price, best_offer, floor_price.saturating_sub(best_offer)))
+        }
+
+        /// Returns the median price of kitties for sale, or `None` if nothing is for sale.
+        ///
+        /// Only the first `MAX_PRICE_SCAN` listings are considered to bound the gas cost.
+        #[ink(message)]
+        pub fn median_price(&self) -> Option<u128> {
+            let mut prices: Vec<u128> = self
+                .kitty_ids_for_sale
+                .iter()
+                .take(MAX_PRICE_SCAN)
+                .filter_map(|&id| self.kitties_for_sale.get(id))
+                .collect();
+            if prices.is_empty() {
+                return None;
+            }
+
+            prices.sort_unstable();
+            let mid = prices.len() / 2;
+            if prices.len() % 2 == 1 {
+                Some(prices[mid])
+            } else {
+                let (low, high) = (prices[mid - 1], prices[mid]);
+                Some(low + (high - low) / 2)
+            }
+        }
+
+        /// List a kitty for adoption, optionally asking the adopter for a `fee`
+        #[ink(message)]
+        pub fn list_for_adoption(&mut self, kitty_id: KittyId, fee: Option<u128>) -> Result<()> {
+            self.ensure_adopt_cooldown_passed(kitty_id)?;
+
             let caller = self.env().caller();
             let owner = self.kitties.owner_of(kitty_id);
 
             if owner != Some(caller) {
                 return Err(Error::NotOwner);
             }
-            let owner = owner.expect("owner is valid");
+            let owner = owner.ok_or(Error::NoOwner)?;
+
+            self.ensure_lister_balance(owner)?;
 
             if self.kitties_for_adoption.contains(&kitty_id) {
                 return Err(Error::AlreadyListedForAdoption);
             }
 
-            // TODO: Fix approve call
-            let list_adopt_result = self.kitties.approve(self.kitties_contract_account, kitty_id);
-            if list_adopt_result.is_err() {
+            // The owner has to approve the market for the kitty before listing it
+            if !self.market_approved(owner, kitty_id) {
                 return Err(Error::ListAdoptNotApproved);
             }
 
+            self.set_market_lock(kitty_id, true)?;
+
             self.kitties_for_adoption.push(kitty_id);
-            self.kitty_ids_for_sale.retain(|&id| id != kitty_id);
+            if let Some(fee) = fee.filter(|&fee| fee > 0) {
+                self.adoption_fees.insert(kitty_id, &fee);
+            }
+            self.remove_sale_listing(kitty_id);
+            self.adopted_at.remove(kitty_id);
 
             Self::env().emit_event(ListedForAdoption {
                 owner,
@@ -148,56 +842,91 @@ mod kitty_market {
         /// Adopt a kitty
         #[ink(message)]
         pub fn adopt(&mut self, kitty_id: KittyId) -> Result<()> {
+            self.non_reentrant(|market| market.adopt_inner(kitty_id))
+        }
+
+        fn adopt_inner(&mut self, kitty_id: KittyId) -> Result<()> {
             let adopter = self.env().caller();
 
             if !self.kitties_for_adoption.contains(&kitty_id) {
                 return Err(Error::NotForAdoption);
             }
 
-            let owner = self.kitties.owner_of(kitty_id).expect("owner is valid");
-            
+            let owner = self.kitties.owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            let fee = self.adoption_fee(kitty_id);
+
+            if fee > 0 {
+                let payment_result = self.kitty_coin.transfer_from(adopter, owner, fee);
+                if payment_result.is_err() {
+                    return Err(Error::CoinTransferFail);
+                }
+            }
+
             let ownership_transfer_result = self.kitties.transfer_from(owner, adopter, kitty_id); 
             if ownership_transfer_result.is_err() {
                 return Err(Error::OwnershipTransferFail);
             }
 
-            self.kitties_for_adoption.retain(|&id| id != kitty_id);
+            self.remove_adoption_listing(kitty_id);
+            self.record_adoption(owner);
+            if self.adopt_cooldown_blocks > 0 {
+                self.adopted_at.insert(kitty_id, &self.env().block_number());
+            }
 
             Self::env().emit_event(Adopted {
                 adopter,
                 kitty_id,
+                fee,
             });
 
             Ok(())
         }
 
+        /// Returns the fee the adopter of kitty `kitty_id` pays its owner, zero for a free
+        /// adoption
+        #[ink(message)]
+        pub fn adoption_fee(&self, kitty_id: KittyId) -> u128 {
+            self.adoption_fees.get(kitty_id).unwrap_or(0)
+        }
+
+        /// List a kitty for sale, optionally until `expiry_block` after which it can't be
+        /// bought anymore
         #[ink(message)]
-        pub fn list_for_sale(&mut self, kitty_id: KittyId, price: u128) -> Result<()> {
+        pub fn list_for_sale(
+            &mut self,
+            kitty_id: KittyId,
+            price: u128,
+            expiry_block: Option<BlockNumber>,
+        ) -> Result<()> {
             let caller = self.env().caller();
             let owner = self.kitties.owner_of(kitty_id);
 
             if owner != Some(caller) {
                 return Err(Error::NotOwner);
             }
-            let owner = owner.expect("owner in valid");
+            let owner = owner.ok_or(Error::NoOwner)?;
 
-            if price == 0 {
-                return Err(Error::PriceIsZero);
-            }
+            self.ensure_lister_balance(owner)?;
+            self.ensure_sale_price(price)?;
 
-            if self.kitties_for_sale.contains(kitty_id) {
+            if self.listed_for_sale(kitty_id) {
                 return Err(Error::AlreadyListedForSale);
             }
 
-            // TODO: Fix approve call
-            let approve_result = self.kitties.approve(self.kitties_contract_account, kitty_id);
-            if approve_result.is_err() {
+            // The owner has to approve the market for the kitty before listing it
+            if !self.market_approved(owner, kitty_id) {
                 return Err(Error::ListSaleNotApproved);
             }
 
+            self.set_market_lock(kitty_id, true)?;
+
             self.kitties_for_sale.insert(kitty_id, &price);
             self.kitty_ids_for_sale.push(kitty_id);
-            self.kitties_for_adoption.retain(|&id| id != kitty_id);
+            self.add_seller_listing(owner, kitty_id);
+            if let Some(expiry_block) = expiry_block {
+                self.sale_expiry.insert(kitty_id, &expiry_block);
+            }
+            self.remove_adoption_listing(kitty_id);
 
             Self::env().emit_event(ListedForSale {
                 owner,
@@ -208,149 +937,3319 @@ mod kitty_market {
             Ok(())
         }
 
+        /// List a kitty for sale with a description of at most `MAX_DESCRIPTION_LEN` bytes
         #[ink(message)]
-        pub fn buy(&mut self, kitty_id: KittyId) -> Result<()> {
-            let buyer = self.env().caller();
-
-            // Check if the kitty is listed for sale
-            if !self.kitties_for_sale.contains(kitty_id) {
-                return Err(Error::NotForSale);
-            }
-            let price = self.kitties_for_sale.get(kitty_id).expect("kitty price should be valid");
-            
-            let maybe_owner = self.kitties.owner_of(kitty_id);
-            if maybe_owner.is_none() {
-                return Err(Error::NoOwner);
+        pub fn list_for_sale_with_description(
+            &mut self,
+            kitty_id: KittyId,
+            price: u128,
+            description: Vec<u8>,
+        ) -> Result<()> {
+            if description.len() > MAX_DESCRIPTION_LEN {
+                return Err(Error::DescriptionTooLong);
             }
-            let seller = maybe_owner.expect("owner should be valid");
 
-            let payment_result = self.kitty_coin.transfer_from(buyer, seller, price);
-            if payment_result.is_err() {
-                return Err(Error::CoinTransferFail);
+            self.list_for_sale(kitty_id, price, None)?;
+            if !description.is_empty() {
+                self.listing_descriptions.insert(kitty_id, &description);
             }
 
-            // TODO: Remove this, change kitty_id from u32 to a random value, and update kitties logic
-            // self.minted_count += 1;
-            // let mint_res = self.kitties.mint(self.minted_count);
-            // if mint_res.is_err() {
-            //     return Err(Error::MintFail);
-            // }
-
-            let ownership_transfer_result = self.kitties.transfer_from(seller, buyer, kitty_id);
-            if ownership_transfer_result.is_err() {
-                return Err(Error::OwnershipTransferFail);
+            Ok(())
+        }
+
+        /// List a kitty for sale and opt it in to abandonment
+        ///
+        /// Once abandonment is allowed, anyone can burn the kitty through `reap_abandoned`
+        /// if its listing stays inactive for more than `abandonment_blocks`.
+        #[ink(message)]
+        pub fn list_for_sale_abandonable(&mut self, kitty_id: KittyId, price: u128) -> Result<()> {
+            self.list_for_sale(kitty_id, price, None)?;
+            self.abandonable_since.insert(kitty_id, &self.env().block_number());
+            Ok(())
+        }
+
+        /// Mint kitty `kitty_id` for the caller and list it for sale at `price` in one go
+        ///
+        /// The caller pays the mint price like for a direct mint, so it has to approve the
+        /// kitties contract for the payment first. The market must be allowlisted on the
+        /// kitties contract. If the kitty can't be listed, it isn't minted either.
+        #[ink(message)]
+        pub fn mint_and_list(&mut self, kitty_id: KittyId, price: u128) -> Result<()> {
+            let caller = self.env().caller();
+            self.ensure_lister_balance(caller)?;
+            self.ensure_sale_price(price)?;
+
+            let mut kitties: ink::contract_ref!(TKittiesMarket) =
+                self.kitties_contract_account.into();
+            if kitties.mint_for_market(kitty_id, caller).is_err() {
+                return Err(Error::MintFail);
             }
 
-            self.kitties_for_sale.remove(kitty_id);
-            self.kitty_ids_for_sale.retain(|&id| id != kitty_id);
+            self.list_for_sale(kitty_id, price, None)
+        }
+
+        /// Burns the kitties among `kitty_ids` whose listing is abandoned, and returns how
+        /// many were burned
+        ///
+        /// Only kitties opted in at listing time qualify, and only while abandonment is
+        /// allowed. Anyone can call this.
+        #[ink(message)]
+        pub fn reap_abandoned(&mut self, kitty_ids: Vec<KittyId>) -> u32 {
+            // A reentrant call reaps nothing
+            self.non_reentrant(|market| Ok(market.reap_abandoned_inner(kitty_ids)))
+                .unwrap_or(0)
+        }
+
+        fn reap_abandoned_inner(&mut self, kitty_ids: Vec<KittyId>) -> u32 {
+            if !self.allow_abandonment {
+                return 0;
+            }
+
+            let mut reaped = 0;
+            for kitty_id in kitty_ids {
+                let Some(since) = self.abandonable_since.get(kitty_id) else {
+                    continue;
+                };
+                let inactive = self.env().block_number().saturating_sub(since);
+                if !self.kitties_for_sale.contains(kitty_id) || inactive <= self.abandonment_blocks
+                {
+                    continue;
+                }
+
+                let mut kitties: ink::contract_ref!(TKittiesMarket) =
+                    self.kitties_contract_account.into();
+                if kitties.burn_for_market(kitty_id).is_err() {
+                    continue;
+                }
+
+                self.remove_sale_listing(kitty_id);
+                reaped += 1;
+
+                Self::env().emit_event(KittyAbandoned { kitty_id });
+            }
+            reaped
+        }
+
+        /// Returns the description of a kitty listed for sale
+        #[ink(message)]
+        pub fn listing_description(&self, kitty_id: KittyId) -> Option<Vec<u8>> {
+            self.listing_descriptions.get(kitty_id)
+        }
+
+        #[ink(message)]
+        pub fn buy(&mut self, kitty_id: KittyId) -> Result<()> {
+            self.non_reentrant(|market| market.buy_inner(kitty_id))
+        }
+
+        fn buy_inner(&mut self, kitty_id: KittyId) -> Result<()> {
+            let buyer = self.env().caller();
+
+            // Check if the kitty is listed for sale
+            if !self.kitties_for_sale.contains(kitty_id) {
+                return Err(Error::NotForSale);
+            }
+            let price = self.kitties_for_sale.get(kitty_id).expect("kitty price should be valid");
+            if self.sale_expired(kitty_id) {
+                return Err(Error::ListingExpired);
+            }
+
+            let seller = self.kitties.owner_of(kitty_id).ok_or(Error::NoOwner)?;
+
+            let deductions = self.pay_for_kitty(kitty_id, buyer, seller, price)?;
+
+            // TODO: Remove this, change kitty_id from u32 to a random value, and update kitties logic
+            // self.minted_count += 1;
+            // let mint_res = self.kitties.mint(self.minted_count);
+            // if mint_res.is_err() {
+            //     return Err(Error::MintFail);
+            // }
+
+            self.kitties
+                .transfer_from(seller, buyer, kitty_id)
+                .map_err(Self::ownership_error)?;
+
+            self.remove_sale_listing(kitty_id);
+            self.record_sale(seller);
 
             Self::env().emit_event(Sold {
                 seller,
                 buyer,
                 kitty_id,
                 price,
+                fee: deductions.fee,
+                royalty_receiver: deductions.royalty_receiver,
+                royalty: deductions.royalty,
             });
+            self.notify_sale_hook(kitty_id, seller, buyer, price);
 
             Ok(())
         }
 
-        // TODO: Add a call to unlist kitty from adoption list
-        // TODO: Add a call to unlist kitty from sale list
-    }
+        /// List a kitty in a dutch auction whose price declines linearly from `start_price`
+        /// to `end_price` over `duration_blocks` blocks
+        #[ink(message)]
+        pub fn list_dutch_auction(
+            &mut self,
+            kitty_id: KittyId,
+            start_price: u128,
+            end_price: u128,
+            duration_blocks: BlockNumber,
+        ) -> Result<()> {
+            if start_price == 0 {
+                return Err(Error::PriceIsZero);
+            }
+            if end_price > start_price {
+                return Err(Error::InvalidAuction);
+            }
+            if self.listed_for_sale(kitty_id) {
+                return Err(Error::AlreadyListedForSale);
+            }
+
+            let owner = self.ensure_kitty_owner(kitty_id)?;
+            self.ensure_lister_balance(owner)?;
+            if !self.market_approved(owner, kitty_id) {
+                return Err(Error::ListSaleNotApproved);
+            }
+
+            self.set_market_lock(kitty_id, true)?;
+
+            let auction = DutchAuction {
+                start_price,
+                end_price,
+                start_block: self.env().block_number(),
+                duration_blocks,
+            };
+            self.dutch_auctions.insert(kitty_id, &auction);
+            self.remove_adoption_listing(kitty_id);
+
+            Self::env().emit_event(ListedForDutchAuction {
+                owner,
+                kitty_id,
+                start_price,
+                end_price,
+                duration_blocks,
+            });
+
+            Ok(())
+        }
+
+        /// Returns the current price of a kitty listed in a dutch auction
+        ///
+        /// Once the auction duration has passed the price stays at the end price
+        #[ink(message)]
+        pub fn current_dutch_price(&self, kitty_id: KittyId) -> Option<u128> {
+            let auction = self.dutch_auctions.get(kitty_id)?;
+
+            let elapsed = self.env().block_number().saturating_sub(auction.start_block);
+            if elapsed >= auction.duration_blocks {
+                return Some(auction.end_price);
+            }
+
+            let discount = (auction.start_price - auction.end_price)
+                .saturating_mul(elapsed.into())
+                / u128::from(auction.duration_blocks);
+            Some(auction.start_price - discount)
+        }
+
+        /// Buy a kitty listed in a dutch auction at its current price
+        #[ink(message)]
+        pub fn buy_dutch(&mut self, kitty_id: KittyId) -> Result<()> {
+            self.non_reentrant(|market| market.buy_dutch_inner(kitty_id))
+        }
+
+        fn buy_dutch_inner(&mut self, kitty_id: KittyId) -> Result<()> {
+            let buyer = self.env().caller();
+            let price = self.current_dutch_price(kitty_id).ok_or(Error::NotForSale)?;
+            let seller = self.kitties.owner_of(kitty_id).ok_or(Error::NoOwner)?;
+
+            let deductions = self.pay_for_kitty(kitty_id, buyer, seller, price)?;
+
+            self.kitties
+                .transfer_from(seller, buyer, kitty_id)
+                .map_err(Self::ownership_error)?;
+
+            self.dutch_auctions.remove(kitty_id);
+
+            Self::env().emit_event(Sold {
+                seller,
+                buyer,
+                kitty_id,
+                price,
+                fee: deductions.fee,
+                royalty_receiver: deductions.royalty_receiver,
+                royalty: deductions.royalty,
+            });
+            self.notify_sale_hook(kitty_id, seller, buyer, price);
+
+            Ok(())
+        }
+
+        /// List a kitty in an english auction taking bids of at least `reserve_price` until
+        /// `end_block`
+        #[ink(message)]
+        pub fn list_auction(
+            &mut self,
+            kitty_id: KittyId,
+            reserve_price: u128,
+            end_block: BlockNumber,
+        ) -> Result<()> {
+            if end_block <= self.env().block_number() {
+                return Err(Error::InvalidAuction);
+            }
+            if self.listed_for_sale(kitty_id) {
+                return Err(Error::AlreadyListedForSale);
+            }
+
+            let owner = self.ensure_kitty_owner(kitty_id)?;
+            self.ensure_lister_balance(owner)?;
+            if !self.market_approved(owner, kitty_id) {
+                return Err(Error::ListSaleNotApproved);
+            }
+
+            self.set_market_lock(kitty_id, true)?;
+
+            let auction = EnglishAuction {
+                seller: owner,
+                reserve_price,
+                end_block,
+                highest_bidder: None,
+                highest_bid: 0,
+            };
+            self.auctions.insert(kitty_id, &auction);
+            self.remove_adoption_listing(kitty_id);
+
+            Self::env().emit_event(ListedForAuction {
+                owner,
+                kitty_id,
+                reserve_price,
+                end_block,
+            });
+
+            Ok(())
+        }
+
+        /// Returns the english auction of a kitty
+        #[ink(message)]
+        pub fn auction(&self, kitty_id: KittyId) -> Option<EnglishAuction> {
+            self.auctions.get(kitty_id)
+        }
+
+        /// Bid `amount` on a kitty in an english auction
+        ///
+        /// The market holds the bid and refunds it once a higher bid comes in
+        #[ink(message)]
+        pub fn place_bid(&mut self, kitty_id: KittyId, amount: u128) -> Result<()> {
+            self.non_reentrant(|market| market.place_bid_inner(kitty_id, amount))
+        }
+
+        fn place_bid_inner(&mut self, kitty_id: KittyId, amount: u128) -> Result<()> {
+            let bidder = self.env().caller();
+            let mut auction = self.auctions.get(kitty_id).ok_or(Error::NotForSale)?;
+            if self.env().block_number() > auction.end_block {
+                return Err(Error::AuctionEnded);
+            }
+            if amount == 0 || amount < auction.reserve_price || amount <= auction.highest_bid {
+                return Err(Error::BidTooLow);
+            }
+
+            let market = self.env().account_id();
+            let escrow_result = self.kitty_coin.transfer_from(bidder, market, amount);
+            if escrow_result.is_err() {
+                return Err(Error::CoinTransferFail);
+            }
+            self.escrowed += amount;
+            if let Some(previous_bidder) = auction.highest_bidder {
+                let refund_result = self.kitty_coin.transfer(previous_bidder, auction.highest_bid);
+                if refund_result.is_err() {
+                    return Err(Error::CoinTransferFail);
+                }
+                self.escrowed = self.escrowed.saturating_sub(auction.highest_bid);
+            }
+
+            auction.highest_bidder = Some(bidder);
+            auction.highest_bid = amount;
+            self.auctions.insert(kitty_id, &auction);
+
+            Self::env().emit_event(BidPlaced {
+                bidder,
+                kitty_id,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Settle an english auction after its end block
+        ///
+        /// The kitty goes to the highest bidder and the bid to the seller. Without bids, or if
+        /// the kitty can no longer be transferred to the winner, the auction just closes and
+        /// any bid is refunded.
+        #[ink(message)]
+        pub fn settle_auction(&mut self, kitty_id: KittyId) -> Result<()> {
+            self.non_reentrant(|market| market.settle_auction_inner(kitty_id))
+        }
+
+        fn settle_auction_inner(&mut self, kitty_id: KittyId) -> Result<()> {
+            let auction = self.auctions.get(kitty_id).ok_or(Error::NotForSale)?;
+            if self.env().block_number() <= auction.end_block {
+                return Err(Error::AuctionNotEnded);
+            }
+
+            self.auctions.remove(kitty_id);
+            let Some(winner) = auction.highest_bidder else {
+                return self.set_market_lock(kitty_id, false);
+            };
+
+            let price = auction.highest_bid;
+            self.escrowed = self.escrowed.saturating_sub(price);
+            if self.kitties.owner_of(kitty_id) != Some(auction.seller) {
+                let refund_result = self.kitty_coin.transfer(winner, price);
+                if refund_result.is_err() {
+                    return Err(Error::CoinTransferFail);
+                }
+                return Ok(());
+            }
+
+            // The seller can still block the transfer, e.g. by revoking the market's
+            // approval. The auction is closed anyway and the winner gets the bid back, so
+            // the bid can never get stuck in the market.
+            let ownership_transfer_result =
+                self.kitties.transfer_from(auction.seller, winner, kitty_id);
+            if ownership_transfer_result.is_err() {
+                let _ = self.set_market_lock(kitty_id, false);
+                let refund_result = self.kitty_coin.transfer(winner, price);
+                if refund_result.is_err() {
+                    return Err(Error::CoinTransferFail);
+                }
+                return Ok(());
+            }
+
+            let deductions = self.pay_out_escrow(kitty_id, auction.seller, price)?;
+
+            Self::env().emit_event(Sold {
+                seller: auction.seller,
+                buyer: winner,
+                kitty_id,
+                price,
+                fee: deductions.fee,
+                royalty_receiver: deductions.royalty_receiver,
+                royalty: deductions.royalty,
+            });
+            self.notify_sale_hook(kitty_id, auction.seller, winner, price);
+
+            Ok(())
+        }
+
+        /// Offer `amount` for a kitty, listed or not
+        ///
+        /// The market holds the offer until it is accepted or cancelled. A new offer replaces
+        /// the caller's previous offer for the kitty, which is refunded.
+        #[ink(message)]
+        pub fn make_offer(&mut self, kitty_id: KittyId, amount: u128) -> Result<()> {
+            self.non_reentrant(|market| market.make_offer_inner(kitty_id, amount))
+        }
+
+        fn make_offer_inner(&mut self, kitty_id: KittyId, amount: u128) -> Result<()> {
+            let bidder = self.env().caller();
+            if amount == 0 {
+                return Err(Error::PriceIsZero);
+            }
+            if self.kitties.owner_of(kitty_id).is_none() {
+                return Err(Error::NoOwner);
+            }
+
+            let market = self.env().account_id();
+            let escrow_result = self.kitty_coin.transfer_from(bidder, market, amount);
+            if escrow_result.is_err() {
+                return Err(Error::CoinTransferFail);
+            }
+            self.escrowed += amount;
+            if let Some(previous_amount) = self.offers.get((kitty_id, bidder)) {
+                let refund_result = self.kitty_coin.transfer(bidder, previous_amount);
+                if refund_result.is_err() {
+                    return Err(Error::CoinTransferFail);
+                }
+                self.escrowed = self.escrowed.saturating_sub(previous_amount);
+            }
+            self.insert_offer(kitty_id, bidder, amount);
+
+            Self::env().emit_event(OfferMade {
+                bidder,
+                kitty_id,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Returns the amount `bidder` offers for a kitty
+        #[ink(message)]
+        pub fn offer(&self, kitty_id: KittyId, bidder: AccountId) -> Option<u128> {
+            self.offers.get((kitty_id, bidder))
+        }
+
+        /// Cancel the caller's offer for a kitty and refund it
+        #[ink(message)]
+        pub fn cancel_offer(&mut self, kitty_id: KittyId) -> Result<()> {
+            self.non_reentrant(|market| market.cancel_offer_inner(kitty_id))
+        }
+
+        fn cancel_offer_inner(&mut self, kitty_id: KittyId) -> Result<()> {
+            let bidder = self.env().caller();
+            let amount = self.take_offer(kitty_id, bidder).ok_or(Error::NoOffer)?;
+
+            let refund_result = self.kitty_coin.transfer(bidder, amount);
+            if refund_result.is_err() {
+                return Err(Error::CoinTransferFail);
+            }
+            self.escrowed = self.escrowed.saturating_sub(amount);
+
+            Self::env().emit_event(OfferCancelled { bidder, kitty_id });
+
+            Ok(())
+        }
+
+        /// Accept the offer of `bidder` for the caller's kitty
+        ///
+        /// The kitty goes to the bidder and the offer to the caller, ending any fixed price
+        /// or adoption listing of the kitty. Other offers for the kitty stay open until
+        /// their bidders cancel them.
+        #[ink(message)]
+        pub fn accept_offer(&mut self, kitty_id: KittyId, bidder: AccountId) -> Result<()> {
+            self.non_reentrant(|market| market.accept_offer_inner(kitty_id, bidder))
+        }
+
+        fn accept_offer_inner(&mut self, kitty_id: KittyId, bidder: AccountId) -> Result<()> {
+            let amount = self.offers.get((kitty_id, bidder)).ok_or(Error::NoOffer)?;
+            // The highest auction bid is held by the market until the auction is settled
+            if self.auctions.contains(kitty_id) {
+                return Err(Error::AlreadyListedForSale);
+            }
+            let seller = self.ensure_kitty_owner(kitty_id)?;
+
+            self.take_offer(kitty_id, bidder);
+            self.escrowed = self.escrowed.saturating_sub(amount);
+            let deductions = self.pay_out_escrow(kitty_id, seller, amount)?;
+
+            let ownership_transfer_result = self.kitties.transfer_from(seller, bidder, kitty_id);
+            if ownership_transfer_result.is_err() {
+                return Err(Error::OwnershipTransferFail);
+            }
+
+            self.remove_sale_listing(kitty_id);
+            self.dutch_auctions.remove(kitty_id);
+            self.remove_adoption_listing(kitty_id);
+
+            Self::env().emit_event(Sold {
+                seller,
+                buyer: bidder,
+                kitty_id,
+                price: amount,
+                fee: deductions.fee,
+                royalty_receiver: deductions.royalty_receiver,
+                royalty: deductions.royalty,
+            });
+            self.notify_sale_hook(kitty_id, seller, bidder, amount);
+
+            Ok(())
+        }
+
+        /// Returns the last block kitty `kitty_id` can be bought at, or `None` if its
+        /// listing doesn't expire
+        #[ink(message)]
+        pub fn sale_expiry(&self, kitty_id: KittyId) -> Option<BlockNumber> {
+            self.sale_expiry.get(kitty_id)
+        }
+
+        /// Removes the expired sale listings among `kitty_ids`
+        ///
+        /// Anyone can call this.
+        #[ink(message)]
+        pub fn sweep_expired(&mut self, kitty_ids: Vec<KittyId>) {
+            for kitty_id in kitty_ids {
+                if !self.kitties_for_sale.contains(kitty_id) || !self.sale_expired(kitty_id) {
+                    continue;
+                }
+
+                // A kitty without owner can't be unlocked, unlist it anyway.
+                let _ = self.set_market_lock(kitty_id, false);
+                self.remove_sale_listing(kitty_id);
+
+                if let Some(owner) = self.kitties.owner_of(kitty_id) {
+                    Self::env().emit_event(UnlistedForSale { owner, kitty_id });
+                }
+            }
+        }
+
+        /// Update the price of a kitty listed for sale
+        #[ink(message)]
+        pub fn update_sale_price(&mut self, kitty_id: KittyId, new_price: u128) -> Result<()> {
+            let old_price = self.kitties_for_sale.get(kitty_id).ok_or(Error::NotForSale)?;
+            self.ensure_sale_price(new_price)?;
+            self.ensure_kitty_owner(kitty_id)?;
+
+            self.kitties_for_sale.insert(kitty_id, &new_price);
+            if self.abandonable_since.contains(kitty_id) {
+                self.abandonable_since.insert(kitty_id, &self.env().block_number());
+            }
+
+            Self::env().emit_event(PriceUpdated {
+                kitty_id,
+                old_price,
+                new_price,
+            });
+
+            Ok(())
+        }
+
+        /// Unlist a kitty from adoption
+        #[ink(message)]
+        pub fn unlist_from_adoption(&mut self, kitty_id: KittyId) -> Result<()> {
+            if !self.kitties_for_adoption.contains(&kitty_id) {
+                return Err(Error::NotForAdoption);
+            }
+            let owner = self.ensure_kitty_owner(kitty_id)?;
+
+            self.set_market_lock(kitty_id, false)?;
+            self.remove_adoption_listing(kitty_id);
+
+            Self::env().emit_event(UnlistedForAdoption {
+                owner,
+                kitty_id,
+            });
+
+            Ok(())
+        }
+
+        /// Unlist a kitty from sale
+        #[ink(message)]
+        pub fn unlist_from_sale(&mut self, kitty_id: KittyId) -> Result<()> {
+            if !self.kitties_for_sale.contains(kitty_id) {
+                return Err(Error::NotForSale);
+            }
+            let owner = self.ensure_kitty_owner(kitty_id)?;
+
+            self.set_market_lock(kitty_id, false)?;
+            self.remove_sale_listing(kitty_id);
+
+            Self::env().emit_event(UnlistedForSale {
+                owner,
+                kitty_id,
+            });
+
+            Ok(())
+        }
+
+        /// Returns whether kitty `kitty_id` still has an owner and the market is still
+        /// approved to transfer it
+        #[ink(message)]
+        pub fn listing_health(&self, kitty_id: KittyId) -> ListingHealth {
+            match self.kitties.owner_of(kitty_id) {
+                Some(owner) => ListingHealth {
+                    owner_ok: true,
+                    approval_ok: self.market_approved(owner, kitty_id),
+                },
+                None => ListingHealth {
+                    owner_ok: false,
+                    approval_ok: false,
+                },
+            }
+        }
+
+        /// Delists kitties listed at a fixed price or in a dutch auction whose owner or
+        /// market approval no longer supports a sale, and returns how many were delisted
+        ///
+        /// Anyone can call this. English auctions hold bids and are left to `settle_auction`.
+        #[ink(message)]
+        pub fn reap_unhealthy(&mut self, kitty_ids: Vec<KittyId>) -> u32 {
+            let mut reaped = 0;
+            for kitty_id in kitty_ids {
+                if !self.kitties_for_sale.contains(kitty_id)
+                    && !self.dutch_auctions.contains(kitty_id)
+                {
+                    continue;
+                }
+                let health = self.listing_health(kitty_id);
+                if health.owner_ok && health.approval_ok {
+                    continue;
+                }
 
-    // #[cfg(test)]
-    // mod tests {
-    //     use super::*;
- 
-    //     /// We test if the default constructor does its job.
-    //     #[ink::test]
-    //     fn default_works() {
-    //         let kitties = Kitties::new();
-    //         let kitty_coin = KittyCoin::new(10_000);
-    //         let kitty_market = KittyMarket::new(kitties, kitty_coin);
-    //         assert_eq!(kitty_market.get(), false);
-    //     }
-
-    //     // /// We test a simple use case of our contract.
-    //     // #[ink::test]
-    //     // fn list_for_adoption_should_work() {
-    //     //     let mut kitty_market = KittyMarket::new(false);
-    //     //     assert_eq!(kitty_market.get(), false);
-    //     //     kitty_market.flip();
-    //     //     assert_eq!(kitty_market.get(), true);
-    //     // }
-    // }
-
-
-    // /// This is how you'd write end-to-end (E2E) or integration tests for ink! contracts.
-    // ///
-    // /// When running these you need to make sure that you:
-    // /// - Compile the tests with the `e2e-tests` feature flag enabled (`--features e2e-tests`)
-    // /// - Are running a Substrate node which contains `pallet-contracts` in the background
-    // #[cfg(all(test, feature = "e2e-tests"))]
-    // mod e2e_tests {
-    //     /// Imports all the definitions from the outer scope so we can use them here.
-    //     use super::*;
-
-    //     /// A helper function used for calling contract messages.
-    //     use ink_e2e::build_message;
-
-    //     /// The End-to-End test `Result` type.
-    //     type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
-
-    //     /// We test that we can upload and instantiate the contract using its default constructor.
-    //     #[ink_e2e::test]
-    //     async fn default_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
-    //         // Given
-    //         let constructor = KittyMarketRef::default();
-
-    //         // When
-    //         let contract_account_id = client
-    //             .instantiate("kitty_market", &ink_e2e::alice(), constructor, 0, None)
-    //             .await
-    //             .expect("instantiate failed")
-    //             .account_id;
-
-    //         // Then
-    //         let get = build_message::<KittyMarketRef>(contract_account_id.clone())
-    //             .call(|kitty_market| kitty_market.get());
-    //         let get_result = client.call_dry_run(&ink_e2e::alice(), &get, 0, None).await;
-    //         assert!(matches!(get_result.return_value(), false));
-
-    //         Ok(())
-    //     }
-
-    //     /// We test that we can read and write a value from the on-chain contract contract.
-    //     #[ink_e2e::test]
-    //     async fn it_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
-    //         // Given
-    //         let constructor = KittyMarketRef::new(false);
-    //         let contract_account_id = client
-    //             .instantiate("kitty_market", &ink_e2e::bob(), constructor, 0, None)
-    //             .await
-    //             .expect("instantiate failed")
-    //             .account_id;
-
-    //         let get = build_message::<KittyMarketRef>(contract_account_id.clone())
-    //             .call(|kitty_market| kitty_market.get());
-    //         let get_result = client.call_dry_run(&ink_e2e::bob(), &get, 0, None).await;
-    //         assert!(matches!(get_result.return_value(), false));
-
-    //         // When
-    //         let flip = build_message::<KittyMarketRef>(contract_account_id.clone())
-    //             .call(|kitty_market| kitty_market.flip());
-    //         let _flip_result = client
-    //             .call(&ink_e2e::bob(), flip, 0, None)
-    //             .await
-    //             .expect("flip failed");
-
-    //         // Then
-    //         let get = build_message::<KittyMarketRef>(contract_account_id.clone())
-    //             .call(|kitty_market| kitty_market.get());
-    //         let get_result = client.call_dry_run(&ink_e2e::bob(), &get, 0, None).await;
-    //         assert!(matches!(get_result.return_value(), true));
-
-    //         Ok(())
-    //     }
-    // }
+                // A kitty without owner can't be unlocked, delist it anyway.
+                let _ = self.set_market_lock(kitty_id, false);
+                self.remove_sale_listing(kitty_id);
+                self.dutch_auctions.remove(kitty_id);
+                reaped += 1;
+
+                Self::env().emit_event(ListingReaped { kitty_id });
+            }
+            reaped
+        }
+
+        /// Stores the offer of `bidder` for kitty `kitty_id`, indexing it if it is new.
+        fn insert_offer(&mut self, kitty_id: KittyId, bidder: AccountId, amount: u128) {
+            let key = (kitty_id, bidder);
+            if !self.offers.contains(key) {
+                self.offer_keys.insert(self.offer_key_count, &key);
+                self.offer_key_index.insert(key, &self.offer_key_count);
+                self.offer_key_count += 1;
+                let count = self.offer_count.get(kitty_id).unwrap_or(0);
+                self.offer_count.insert(kitty_id, &(count + 1));
+            }
+            self.offers.insert(key, &amount);
+        }
+
+        /// Removes the offer of `bidder` for kitty `kitty_id` and returns its amount.
+        ///
+        /// The last indexed offer moves into the index of the removed one.
+        fn take_offer(&mut self, kitty_id: KittyId, bidder: AccountId) -> Option<u128> {
+            let key = (kitty_id, bidder);
+            let amount = self.offers.take(key)?;
+
+            if let Some(index) = self.offer_key_index.take(key) {
+                self.offer_key_count -= 1;
+                let last_key = self.offer_keys.take(self.offer_key_count);
+                if let Some(last_key) = last_key.filter(|_| index != self.offer_key_count) {
+                    self.offer_keys.insert(index, &last_key);
+                    self.offer_key_index.insert(last_key, &index);
+                }
+            }
+            let count = self.offer_count.get(kitty_id).unwrap_or(0);
+            if count > 1 {
+                self.offer_count.insert(kitty_id, &(count - 1));
+            } else {
+                self.offer_count.remove(kitty_id);
+            }
+
+            Some(amount)
+        }
+
+        /// Counts a completed `buy` for `seller`.
+        fn record_sale(&mut self, seller: AccountId) {
+            let sales = self.sales_by_seller(seller).saturating_add(1);
+            self.sales_by_seller.insert(seller, &sales);
+        }
+
+        /// Counts a completed adoption for `lister`.
+        fn record_adoption(&mut self, lister: AccountId) {
+            let adoptions = self.adoptions_by_lister(lister).saturating_add(1);
+            self.adoptions_by_lister.insert(lister, &adoptions);
+        }
+
+        /// Records `seller` as the seller of kitty `kitty_id` listed for sale.
+        fn add_seller_listing(&mut self, seller: AccountId, kitty_id: KittyId) {
+            let mut listings = self.seller_listings.get(seller).unwrap_or_default();
+            listings.push(kitty_id);
+            self.seller_listings.insert(seller, &listings);
+            self.sale_sellers.insert(kitty_id, &seller);
+        }
+
+        /// Removes the sale listing of kitty `kitty_id` with everything stored about it.
+        fn remove_sale_listing(&mut self, kitty_id: KittyId) {
+            self.kitties_for_sale.remove(kitty_id);
+            self.kitty_ids_for_sale.retain(|&id| id != kitty_id);
+            self.listing_descriptions.remove(kitty_id);
+            self.sale_expiry.remove(kitty_id);
+            self.abandonable_since.remove(kitty_id);
+
+            let Some(seller) = self.sale_sellers.take(kitty_id) else {
+                return;
+            };
+            let mut listings = self.seller_listings.get(seller).unwrap_or_default();
+            listings.retain(|&id| id != kitty_id);
+            if listings.is_empty() {
+                self.seller_listings.remove(seller);
+            } else {
+                self.seller_listings.insert(seller, &listings);
+            }
+        }
+
+        /// Removes the adoption listing of kitty `kitty_id` with its fee.
+        fn remove_adoption_listing(&mut self, kitty_id: KittyId) {
+            self.kitties_for_adoption.retain(|&id| id != kitty_id);
+            self.adoption_fees.remove(kitty_id);
+        }
+
+        /// Runs `f` unless a guarded message is already running, failing with
+        /// `Reentrancy` if it is.
+        ///
+        /// Messages moving coins or kitties check their inputs first and mostly update the
+        /// market state after their external calls, since an error reverts those calls
+        /// and the state together. The state is stale while the calls run, so a
+        /// reentering message could act on a listing that is already paid for. This guard
+        /// keeps it out.
+        fn non_reentrant<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+            if self.locked {
+                return Err(Error::Reentrancy);
+            }
+            self.locked = true;
+            let result = f(self);
+            self.locked = false;
+            result
+        }
+
+        /// Returns the part of the vesting proceeds of `seller` vested so far, claimed or not.
+        fn vested_proceeds(&self, seller: AccountId) -> u128 {
+            let Some((amount, start)) = self.vesting_proceeds.get(seller) else {
+                return 0;
+            };
+
+            let elapsed = self.env().block_number().saturating_sub(start);
+            if elapsed >= self.vesting_blocks {
+                amount
+            } else {
+                amount.saturating_mul(elapsed.into()) / u128::from(self.vesting_blocks)
+            }
+        }
+
+        /// Starts vesting `amount` of sale proceeds for `seller` from the current block.
+        ///
+        /// Proceeds already vested stay claimable right away. Only the part of the previous
+        /// proceeds that is still vesting vests again together with the new ones.
+        fn vest_proceeds(&mut self, seller: AccountId, amount: u128) {
+            let mut still_vesting = 0;
+            if let Some((vesting, _)) = self.vesting_proceeds.get(seller) {
+                let vested = self.vested_proceeds(seller);
+                let claimed = self.claimed_proceeds.get(seller).unwrap_or(0);
+                let released = self.released_proceeds.get(seller).unwrap_or(0);
+                let released = released + vested.saturating_sub(claimed);
+                if released > 0 {
+                    self.released_proceeds.insert(seller, &released);
+                }
+                still_vesting = vesting - vested;
+            }
+
+            self.vesting_proceeds
+                .insert(seller, &(still_vesting + amount, self.env().block_number()));
+            self.claimed_proceeds.remove(seller);
+            self.escrowed += amount;
+        }
+
+        /// Returns an error if kitty `kitty_id` was adopted less than the adoption cooldown ago
+        fn ensure_adopt_cooldown_passed(&self, kitty_id: KittyId) -> Result<()> {
+            if let Some(adopted_at) = self.adopted_at.get(kitty_id) {
+                let elapsed = self.env().block_number().saturating_sub(adopted_at);
+                if elapsed < self.adopt_cooldown_blocks {
+                    return Err(Error::AdoptCooldown);
+                }
+            }
+            Ok(())
+        }
+
+        /// Pays `price` from `buyer` for kitty `kitty_id` of `seller`, splitting off the
+        /// market commission and the royalty, and returns them
+        ///
+        /// Fails before moving any coin unless `buyer` approved the market for `price`.
+        fn pay_for_kitty(
+            &mut self,
+            kitty_id: KittyId,
+            buyer: AccountId,
+            seller: AccountId,
+            price: u128,
+        ) -> Result<Deductions> {
+            if self.kitty_coin.allowance(buyer, self.env().account_id()) < price {
+                return Err(Error::InsufficientAllowance);
+            }
+
+            let deductions = self.sale_deductions(kitty_id, price);
+            if deductions.fee > 0 {
+                self.kitty_coin
+                    .transfer_from(buyer, self.fee_recipient, deductions.fee)
+                    .map_err(Self::payment_error)?;
+            }
+            if deductions.royalty > 0 {
+                self.kitty_coin
+                    .transfer_from(buyer, deductions.royalty_receiver, deductions.royalty)
+                    .map_err(Self::payment_error)?;
+            }
+
+            // With vesting enabled the market holds the proceeds until the seller claims them
+            let proceeds = price - deductions.fee - deductions.royalty;
+            let payment_result = if self.vesting_blocks == 0 {
+                self.kitty_coin.transfer_from(buyer, seller, proceeds)
+            } else {
+                self.kitty_coin.transfer_from(buyer, self.env().account_id(), proceeds)
+            };
+            payment_result.map_err(Self::payment_error)?;
+            if self.vesting_blocks > 0 {
+                self.vest_proceeds(seller, proceeds);
+            }
+
+            Ok(deductions)
+        }
+
+        /// Maps a failed kitty coin payment by a buyer to the market error explaining it
+        fn payment_error(error: trait_erc20::Error) -> Error {
+            match error {
+                trait_erc20::Error::BalanceTooLow => Error::InsufficientBalance,
+                trait_erc20::Error::AllowanceTooLow => Error::InsufficientAllowance,
+                _ => Error::CoinTransferFail,
+            }
+        }
+
+        /// Maps a failed kitty transfer to a buyer to the market error explaining it
+        fn ownership_error(error: trait_erc721::Error) -> Error {
+            match error {
+                trait_erc721::Error::NotApproved => Error::TransferNotApproved,
+                trait_erc721::Error::TokenNotFound => Error::KittyNotFound,
+                _ => Error::OwnershipTransferFail,
+            }
+        }
+
+        /// Pays `price` held by the market for kitty `kitty_id` of `seller`, splitting off
+        /// the market commission and the royalty, and returns them
+        fn pay_out_escrow(
+            &mut self,
+            kitty_id: KittyId,
+            seller: AccountId,
+            price: u128,
+        ) -> Result<Deductions> {
+            let deductions = self.sale_deductions(kitty_id, price);
+            if deductions.fee > 0 {
+                let fee_result = self.kitty_coin.transfer(self.fee_recipient, deductions.fee);
+                if fee_result.is_err() {
+                    return Err(Error::CoinTransferFail);
+                }
+            }
+            if deductions.royalty > 0 {
+                let royalty_result = self
+                    .kitty_coin
+                    .transfer(deductions.royalty_receiver, deductions.royalty);
+                if royalty_result.is_err() {
+                    return Err(Error::CoinTransferFail);
+                }
+            }
+
+            // With vesting enabled the market keeps holding the proceeds
+            let proceeds = price - deductions.fee - deductions.royalty;
+            if self.vesting_blocks == 0 {
+                let payment_result = self.kitty_coin.transfer(seller, proceeds);
+                if payment_result.is_err() {
+                    return Err(Error::CoinTransferFail);
+                }
+            } else {
+                self.vest_proceeds(seller, proceeds);
+            }
+
+            Ok(deductions)
+        }
+
+        /// Returns the market commission and the royalty the kitties contract asks for
+        /// on a sale of kitty `kitty_id` for `price`
+        ///
+        /// The royalty is capped at what is left of the price after the commission.
+        fn sale_deductions(&self, kitty_id: KittyId, price: u128) -> Deductions {
+            let fee = self.sale_fee(price);
+            let kitties: ink::contract_ref!(TERC2981) = self.kitties_contract_account.into();
+            let (royalty_receiver, royalty) = kitties.royalty_info(kitty_id, price);
+            Deductions {
+                fee,
+                royalty_receiver,
+                royalty: royalty.min(price - fee),
+            }
+        }
+
+        /// Returns `true` if kitty `kitty_id` is listed for sale, at a fixed price or in an
+        /// auction
+        fn listed_for_sale(&self, kitty_id: KittyId) -> bool {
+            self.kitties_for_sale.contains(kitty_id)
+                || self.dutch_auctions.contains(kitty_id)
+                || self.auctions.contains(kitty_id)
+        }
+
+        /// Returns `true` if the sale listing of kitty `kitty_id` has expired
+        fn sale_expired(&self, kitty_id: KittyId) -> bool {
+            self.sale_expiry
+                .get(kitty_id)
+                .is_some_and(|expiry_block| self.env().block_number() > expiry_block)
+        }
+
+        /// Notifies the sale hook about a sale
+        ///
+        /// A failing hook is ignored so it can't block sales
+        fn notify_sale_hook(
+            &self,
+            kitty_id: KittyId,
+            seller: AccountId,
+            buyer: AccountId,
+            price: u128,
+        ) {
+            let Some(sale_hook) = self.sale_hook else {
+                return;
+            };
+
+            let mut sale_hook: ink::contract_ref!(TKittySaleHook) = sale_hook.into();
+            let _ = sale_hook
+                .call_mut()
+                .on_kitty_sold(kitty_id, seller, buyer, price)
+                .try_invoke();
+        }
+
+        /// Returns the commission taken from a sale at `price`
+        fn sale_fee(&self, price: u128) -> u128 {
+            price.saturating_mul(self.fee_bps.into()) / 10_000
+        }
+
+        /// Returns the owner of kitty `kitty_id`, or an error if the caller is not its owner
+        fn ensure_kitty_owner(&self, kitty_id: KittyId) -> Result<AccountId> {
+            let caller = self.env().caller();
+            if self.kitties.owner_of(kitty_id) != Some(caller) {
+                return Err(Error::NotOwner);
+            }
+            Ok(caller)
+        }
+
+        /// Returns `true` if the market can transfer kitty `kitty_id` of `owner`
+        fn market_approved(&self, owner: AccountId, kitty_id: KittyId) -> bool {
+            let market = self.env().account_id();
+            self.kitties.get_approved(kitty_id) == Some(market)
+                || self.kitties.is_approved_for_all(owner, market)
+        }
+
+        /// Returns an error if `lister` holds less kitty coin than `min_lister_balance`
+        fn ensure_lister_balance(&self, lister: AccountId) -> Result<()> {
+            if self.min_lister_balance > 0
+                && self.kitty_coin.balance_of(lister) < self.min_lister_balance
+            {
+                return Err(Error::InsufficientListerBalance);
+            }
+            Ok(())
+        }
+
+        /// Returns an error if kitties can't be listed for sale at `price`
+        fn ensure_sale_price(&self, price: u128) -> Result<()> {
+            if price == 0 {
+                return Err(Error::PriceIsZero);
+            }
+            if price < self.min_price {
+                return Err(Error::PriceTooLow);
+            }
+            if self.max_price.is_some_and(|max_price| price > max_price) {
+                return Err(Error::PriceTooHigh);
+            }
+            Ok(())
+        }
+
+        /// Returns an error if the caller is not the market owner
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotMarketOwner);
+            }
+            Ok(())
+        }
+
+        /// Locks or unlocks a listed kitty on the kitties contract if `listed_lock` is enabled.
+        /// The kitties contract clears the lock itself when the kitty changes hands.
+        fn set_market_lock(&self, kitty_id: KittyId, locked: bool) -> Result<()> {
+            if !self.listed_lock {
+                return Ok(());
+            }
+
+            let mut kitties: ink::contract_ref!(TKittiesMarket) = self.kitties_contract_account.into();
+            let lock_result = kitties.set_locked_for_market(kitty_id, locked);
+            if lock_result.is_err() {
+                return Err(Error::MarketLockFail);
+            }
+
+            Ok(())
+        }
+    }
+
+    impl TKittyTransferHook for KittyMarket {
+        /// Delists kitty `kitty_id` from fixed-price sale, Dutch auction and adoption
+        /// after it moved from `from` to `to` outside the market or was burned. Only the
+        /// kitties contract can call this, other callers are ignored.
+        ///
+        /// English auctions hold bids and are left to `settle_auction`.
+        #[ink(message)]
+        fn on_kitty_transferred(&mut self, kitty_id: KittyId, from: AccountId, _to: AccountId) {
+            if self.env().caller() != self.kitties_contract_account {
+                return;
+            }
+
+            // The kitties contract already cleared the market lock on transfer.
+            if self.kitties_for_sale.contains(kitty_id) {
+                self.remove_sale_listing(kitty_id);
+                Self::env().emit_event(UnlistedForSale {
+                    owner: from,
+                    kitty_id,
+                });
+            }
+            if self.dutch_auctions.contains(kitty_id) {
+                self.dutch_auctions.remove(kitty_id);
+                Self::env().emit_event(ListingReaped { kitty_id });
+            }
+            if self.kitties_for_adoption.contains(&kitty_id) {
+                self.remove_adoption_listing(kitty_id);
+                Self::env().emit_event(UnlistedForAdoption {
+                    owner: from,
+                    kitty_id,
+                });
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        type Event = <KittyMarket as ::ink::reflect::ContractEventBase>::Type;
+
+        fn ids(raw: &[u32]) -> Vec<KittyId> {
+            raw.iter().copied().map(KittyId::from).collect()
+        }
+
+        fn new_market() -> KittyMarket {
+            KittyMarket::new(
+                AccountId::from([0x1; 32]),
+                AccountId::from([0x2; 32]),
+                0,
+                AccountId::from([0x3; 32]),
+                0,
+                None,
+            )
+        }
+
+        /// Puts a kitty of the caller up for sale without going through the kitties
+        /// contract.
+        fn list(kitty_market: &mut KittyMarket, kitty_id: KittyId, price: u128) {
+            kitty_market.kitties_for_sale.insert(kitty_id, &price);
+            kitty_market.kitty_ids_for_sale.push(kitty_id);
+            let seller = ink::env::caller::<ink::env::DefaultEnvironment>();
+            kitty_market.add_seller_listing(seller, kitty_id);
+        }
+
+        /// Makes an offer without escrowing coins.
+        fn offer(kitty_market: &mut KittyMarket, kitty_id: KittyId, bidder: AccountId, amount: u128) {
+            kitty_market.insert_offer(kitty_id, bidder, amount);
+        }
+
+        #[ink::test]
+        fn expired_listing_cannot_be_bought() {
+            let mut kitty_market = new_market();
+            list(&mut kitty_market, KittyId::from(1), 100);
+            list(&mut kitty_market, KittyId::from(2), 100);
+            kitty_market.sale_expiry.insert(KittyId::from(1), &2);
+            assert_eq!(kitty_market.sale_expiry(KittyId::from(1)), Some(2));
+            assert_eq!(kitty_market.sale_expiry(KittyId::from(2)), None);
+
+            advance_blocks(3);
+            assert_eq!(kitty_market.buy(KittyId::from(1)), Err(Error::ListingExpired));
+
+            // Listings without expiry and unlisted kitties are left alone by the sweep.
+            kitty_market.sweep_expired(ids(&[2, 3]));
+            assert_eq!(kitty_market.sale_price_of(KittyId::from(2)), Some(100));
+        }
+
+        #[ink::test]
+        fn reap_abandoned_requires_opt_in() {
+            let mut kitty_market = new_market();
+            list(&mut kitty_market, KittyId::from(1), 100);
+            advance_blocks(10);
+
+            // Abandonment is disabled by default.
+            assert_eq!(kitty_market.abandonment(), (false, 0));
+            assert_eq!(kitty_market.reap_abandoned(ids(&[1])), 0);
+
+            // Kitty 1 wasn't opted in at listing time.
+            assert_eq!(kitty_market.set_abandonment(true, 5), Ok(()));
+            assert_eq!(kitty_market.reap_abandoned(ids(&[1])), 0);
+            assert_eq!(kitty_market.sale_price_of(KittyId::from(1)), Some(100));
+
+            // An opted in kitty with recent listing activity is kept.
+            list(&mut kitty_market, KittyId::from(2), 100);
+            kitty_market.abandonable_since.insert(KittyId::from(2), &8);
+            assert_eq!(kitty_market.reap_abandoned(ids(&[2])), 0);
+        }
+
+        #[ink::test]
+        fn set_abandonment_requires_market_owner() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(kitty_market.set_abandonment(true, 5), Err(Error::NotMarketOwner));
+            assert_eq!(kitty_market.abandonment(), (false, 0));
+        }
+
+        #[ink::test]
+        fn listing_predicates_work() {
+            let mut kitty_market = new_market();
+            list(&mut kitty_market, KittyId::from(1), 100);
+            kitty_market.kitties_for_adoption.push(KittyId::from(2));
+
+            assert_eq!(kitty_market.sale_price_of(KittyId::from(1)), Some(100));
+            assert!(kitty_market.is_for_sale(KittyId::from(1)));
+            assert!(!kitty_market.is_for_adoption(KittyId::from(1)));
+
+            assert_eq!(kitty_market.sale_price_of(KittyId::from(2)), None);
+            assert!(!kitty_market.is_for_sale(KittyId::from(2)));
+            assert!(kitty_market.is_for_adoption(KittyId::from(2)));
+
+            assert_eq!(kitty_market.sale_price_of(KittyId::from(3)), None);
+            assert!(!kitty_market.is_for_sale(KittyId::from(3)));
+            assert!(!kitty_market.is_for_adoption(KittyId::from(3)));
+        }
+
+        #[ink::test]
+        fn market_involvement_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            let uninvolved = Involvement {
+                for_sale: false,
+                for_adoption: false,
+                has_offers: false,
+                in_auction: false,
+            };
+            assert_eq!(kitty_market.market_involvement(KittyId::from(1)), uninvolved);
+
+            list(&mut kitty_market, KittyId::from(1), 100);
+            offer(&mut kitty_market, KittyId::from(1), accounts.bob, 80);
+            assert_eq!(
+                kitty_market.market_involvement(KittyId::from(1)),
+                Involvement {
+                    for_sale: true,
+                    has_offers: true,
+                    ..uninvolved.clone()
+                }
+            );
+
+            // Offers for other kitties don't count once the kitty's own offer is gone.
+            offer(&mut kitty_market, KittyId::from(3), accounts.bob, 80);
+            kitty_market.take_offer(KittyId::from(1), accounts.bob);
+            assert!(!kitty_market.market_involvement(KittyId::from(1)).has_offers);
+
+            kitty_market.auctions.insert(KittyId::from(2), &auction(accounts.alice, None));
+            assert_eq!(
+                kitty_market.market_involvement(KittyId::from(2)),
+                Involvement {
+                    in_auction: true,
+                    ..uninvolved
+                }
+            );
+        }
+
+        #[ink::test]
+        fn reap_unhealthy_skips_unlisted_kitties() {
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.reap_unhealthy(ids(&[1, 2, 3])), 0);
+        }
+
+        #[ink::test]
+        fn paged_queries_work() {
+            let mut kitty_market = new_market();
+            for kitty_id in 1..=5 {
+                list(&mut kitty_market, KittyId::from(kitty_id), kitty_id as u128 * 10);
+                kitty_market.kitties_for_adoption.push(KittyId::from(kitty_id + 10));
+            }
+            assert_eq!(kitty_market.sale_count(), 5);
+            assert_eq!(kitty_market.adoption_count(), 5);
+
+            assert_eq!(kitty_market.kitties_for_sale_paged(0, 2), vec![(KittyId::from(1), 10), (KittyId::from(2), 20)]);
+            assert_eq!(kitty_market.kitties_for_sale_paged(2, 2), vec![(KittyId::from(3), 30), (KittyId::from(4), 40)]);
+            assert_eq!(kitty_market.kitties_for_sale_paged(4, 2), vec![(KittyId::from(5), 50)]);
+            assert_eq!(kitty_market.kitties_for_sale_paged(6, 2), vec![]);
+
+            assert_eq!(kitty_market.adoption_list_paged(0, 2), ids(&[11, 12]));
+            assert_eq!(kitty_market.adoption_list_paged(2, 2), ids(&[13, 14]));
+            assert_eq!(kitty_market.adoption_list_paged(4, 2), ids(&[15]));
+
+            // Oversized pages are clamped.
+            assert_eq!(kitty_market.kitties_for_sale_paged(0, u32::MAX).len(), 5);
+        }
+
+        #[ink::test]
+        fn bid_ask_spread_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.bid_ask_spread(), None);
+
+            list(&mut kitty_market, KittyId::from(1), 300);
+            list(&mut kitty_market, KittyId::from(2), 200);
+            // No offers yet.
+            assert_eq!(kitty_market.bid_ask_spread(), None);
+
+            offer(&mut kitty_market, KittyId::from(1), accounts.bob, 120);
+            offer(&mut kitty_market, KittyId::from(3), accounts.charlie, 150);
+            assert_eq!(kitty_market.bid_ask_spread(), Some((200, 150, 50)));
+
+            // An offer above the floor price leaves no spread.
+            offer(&mut kitty_market, KittyId::from(2), accounts.django, 250);
+            assert_eq!(kitty_market.bid_ask_spread(), Some((200, 250, 0)));
+        }
+
+        #[ink::test]
+        fn removed_offers_leave_the_index() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            list(&mut kitty_market, KittyId::from(1), 300);
+            offer(&mut kitty_market, KittyId::from(1), accounts.bob, 250);
+            offer(&mut kitty_market, KittyId::from(1), accounts.charlie, 120);
+            offer(&mut kitty_market, KittyId::from(2), accounts.django, 150);
+            // Replacing an offer doesn't index it twice.
+            offer(&mut kitty_market, KittyId::from(1), accounts.charlie, 130);
+            assert_eq!(kitty_market.offer_key_count, 3);
+            assert_eq!(kitty_market.offer_count.get(KittyId::from(1)), Some(2));
+
+            // The last offer takes the place of the removed one.
+            assert_eq!(kitty_market.take_offer(KittyId::from(1), accounts.bob), Some(250));
+            assert_eq!(kitty_market.take_offer(KittyId::from(1), accounts.bob), None);
+            assert_eq!(kitty_market.offer_key_count, 2);
+            assert_eq!(
+                kitty_market.offer_keys.get(0),
+                Some((KittyId::from(2), accounts.django))
+            );
+            assert_eq!(kitty_market.offer_count.get(KittyId::from(1)), Some(1));
+            assert_eq!(kitty_market.bid_ask_spread(), Some((300, 150, 150)));
+
+            assert_eq!(kitty_market.take_offer(KittyId::from(1), accounts.charlie), Some(130));
+            assert_eq!(kitty_market.offer_count.get(KittyId::from(1)), None);
+            assert_eq!(kitty_market.offer_key_count, 1);
+        }
+
+        #[ink::test]
+        fn median_price_works() {
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.median_price(), None);
+
+            // Odd number of listings.
+            list(&mut kitty_market, KittyId::from(1), 30);
+            list(&mut kitty_market, KittyId::from(2), 10);
+            list(&mut kitty_market, KittyId::from(3), 20);
+            assert_eq!(kitty_market.median_price(), Some(20));
+
+            // Even number of listings.
+            list(&mut kitty_market, KittyId::from(4), 50);
+            assert_eq!(kitty_market.median_price(), Some(25));
+        }
+
+        #[ink::test]
+        fn vested_proceeds_release_linearly() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.set_vesting_blocks(10), Ok(()));
+
+            // Bob sells a kitty for 100 coins.
+            kitty_market.vest_proceeds(accounts.bob, 100);
+            assert_eq!(kitty_market.releasable_proceeds(accounts.bob), 0);
+
+            // After 4 blocks 40% of the proceeds are released.
+            advance_blocks(4);
+            assert_eq!(kitty_market.releasable_proceeds(accounts.bob), 40);
+
+            // After the vesting period everything is released.
+            advance_blocks(6);
+            assert_eq!(kitty_market.releasable_proceeds(accounts.bob), 100);
+            advance_blocks(5);
+            assert_eq!(kitty_market.releasable_proceeds(accounts.bob), 100);
+        }
+
+        #[ink::test]
+        fn new_sale_vests_only_unvested_proceeds_again() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.set_vesting_blocks(10), Ok(()));
+
+            kitty_market.vest_proceeds(accounts.bob, 100);
+            advance_blocks(4);
+            // Bob already claimed 30 of the 40 vested coins.
+            kitty_market.claimed_proceeds.insert(accounts.bob, &30);
+            assert_eq!(kitty_market.releasable_proceeds(accounts.bob), 10);
+
+            // A second sale keeps the 10 vested coins claimable and vests the 60 still
+            // vesting together with the new 100.
+            kitty_market.vest_proceeds(accounts.bob, 100);
+            assert_eq!(kitty_market.releasable_proceeds(accounts.bob), 10);
+            advance_blocks(5);
+            assert_eq!(kitty_market.releasable_proceeds(accounts.bob), 90);
+            advance_blocks(5);
+            assert_eq!(kitty_market.releasable_proceeds(accounts.bob), 170);
+        }
+
+        #[ink::test]
+        fn vested_proceeds_stay_released_after_new_sale() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.set_vesting_blocks(10), Ok(()));
+
+            // Bob's first sale has fully vested by the time he sells again.
+            kitty_market.vest_proceeds(accounts.bob, 100);
+            advance_blocks(15);
+            assert_eq!(kitty_market.releasable_proceeds(accounts.bob), 100);
+
+            // Only the new proceeds vest from now on.
+            kitty_market.vest_proceeds(accounts.bob, 50);
+            assert_eq!(kitty_market.releasable_proceeds(accounts.bob), 100);
+            assert_eq!(kitty_market.released_proceeds.get(accounts.bob), Some(100));
+            assert_eq!(kitty_market.vesting_proceeds.get(accounts.bob).map(|(a, _)| a), Some(50));
+            advance_blocks(4);
+            assert_eq!(kitty_market.releasable_proceeds(accounts.bob), 120);
+            advance_blocks(6);
+            assert_eq!(kitty_market.releasable_proceeds(accounts.bob), 150);
+        }
+
+        #[ink::test]
+        fn set_vesting_blocks_requires_market_owner() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(kitty_market.set_vesting_blocks(10), Err(Error::NotMarketOwner));
+            assert_eq!(kitty_market.vesting_blocks(), 0);
+        }
+
+        #[ink::test]
+        fn adopt_cooldown_works() {
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.set_adopt_cooldown_blocks(5), Ok(()));
+
+            // Kitty 1 was just adopted and can't be listed for adoption again.
+            kitty_market.adopted_at.insert(KittyId::from(1), &0);
+            assert_eq!(kitty_market.list_for_adoption(KittyId::from(1), None), Err(Error::AdoptCooldown));
+            advance_blocks(4);
+            assert_eq!(kitty_market.ensure_adopt_cooldown_passed(KittyId::from(1)), Err(Error::AdoptCooldown));
+
+            // After the cooldown it can be listed again.
+            advance_blocks(1);
+            assert_eq!(kitty_market.ensure_adopt_cooldown_passed(KittyId::from(1)), Ok(()));
+            // Kitties never adopted have no cooldown.
+            assert_eq!(kitty_market.ensure_adopt_cooldown_passed(KittyId::from(2)), Ok(()));
+        }
+
+        #[ink::test]
+        fn zero_adopt_cooldown_is_disabled() {
+            let mut kitty_market = new_market();
+            kitty_market.adopted_at.insert(KittyId::from(1), &0);
+            assert_eq!(kitty_market.ensure_adopt_cooldown_passed(KittyId::from(1)), Ok(()));
+        }
+
+        #[ink::test]
+        fn unlisting_unlisted_kitty_fails() {
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.unlist_from_sale(KittyId::from(1)), Err(Error::NotForSale));
+            assert_eq!(kitty_market.unlist_from_adoption(KittyId::from(1)), Err(Error::NotForAdoption));
+        }
+
+        #[ink::test]
+        fn update_sale_price_rejects_invalid_update() {
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.update_sale_price(KittyId::from(1), 50), Err(Error::NotForSale));
+            list(&mut kitty_market, KittyId::from(1), 100);
+            assert_eq!(kitty_market.update_sale_price(KittyId::from(1), 0), Err(Error::PriceIsZero));
+            assert_eq!(kitty_market.kitties_for_sale(), vec![(KittyId::from(1), 100)]);
+        }
+
+        #[ink::test]
+        fn sale_price_bounds_are_enforced() {
+            let mut kitty_market = new_market();
+            kitty_market.min_price = 100;
+            kitty_market.max_price = Some(1_000);
+            assert_eq!(kitty_market.price_bounds(), (100, Some(1_000)));
+            assert_eq!(kitty_market.ensure_sale_price(100), Ok(()));
+            assert_eq!(kitty_market.ensure_sale_price(1_000), Ok(()));
+            assert_eq!(kitty_market.ensure_sale_price(99), Err(Error::PriceTooLow));
+            assert_eq!(kitty_market.ensure_sale_price(1_001), Err(Error::PriceTooHigh));
+
+            list(&mut kitty_market, KittyId::from(1), 500);
+            assert_eq!(kitty_market.update_sale_price(KittyId::from(1), 99), Err(Error::PriceTooLow));
+            assert_eq!(
+                kitty_market.update_sale_price(KittyId::from(1), 1_001),
+                Err(Error::PriceTooHigh)
+            );
+            assert_eq!(kitty_market.mint_and_list(KittyId::from(2), 99), Err(Error::PriceTooLow));
+            assert_eq!(kitty_market.kitties_for_sale(), vec![(KittyId::from(1), 500)]);
+
+            // Without a maximum any price above the minimum goes.
+            kitty_market.max_price = None;
+            assert_eq!(kitty_market.ensure_sale_price(u128::MAX), Ok(()));
+        }
+
+        #[ink::test]
+        fn transfer_failures_are_mapped() {
+            assert_eq!(
+                KittyMarket::payment_error(trait_erc20::Error::BalanceTooLow),
+                Error::InsufficientBalance
+            );
+            assert_eq!(
+                KittyMarket::payment_error(trait_erc20::Error::AllowanceTooLow),
+                Error::InsufficientAllowance
+            );
+            assert_eq!(
+                KittyMarket::payment_error(trait_erc20::Error::Paused),
+                Error::CoinTransferFail
+            );
+            assert_eq!(
+                KittyMarket::ownership_error(trait_erc721::Error::NotApproved),
+                Error::TransferNotApproved
+            );
+            assert_eq!(
+                KittyMarket::ownership_error(trait_erc721::Error::TokenNotFound),
+                Error::KittyNotFound
+            );
+            assert_eq!(
+                KittyMarket::ownership_error(trait_erc721::Error::HoldingLimitReached),
+                Error::OwnershipTransferFail
+            );
+        }
+
+        #[ink::test]
+        fn sale_fee_works() {
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.sale_fee(1_000), 0);
+            kitty_market.fee_bps = 250;
+            assert_eq!(kitty_market.sale_fee(1_000), 25);
+            assert_eq!(kitty_market.sale_fee(39), 0);
+            kitty_market.fee_bps = 10_000;
+            assert_eq!(kitty_market.sale_fee(1_000), 1_000);
+        }
+
+        #[ink::test]
+        fn fee_is_capped() {
+            let kitty_market = KittyMarket::new(
+                AccountId::from([0x1; 32]),
+                AccountId::from([0x2; 32]),
+                10_001,
+                AccountId::from([0x3; 32]),
+                0,
+                None,
+            );
+            assert_eq!(kitty_market.fee(), (10_000, AccountId::from([0x3; 32])));
+        }
+
+        #[ink::test]
+        fn too_long_description_is_rejected() {
+            let mut kitty_market = new_market();
+            let description = vec![b'a'; MAX_DESCRIPTION_LEN + 1];
+            assert_eq!(
+                kitty_market.list_for_sale_with_description(KittyId::from(1), 100, description),
+                Err(Error::DescriptionTooLong)
+            );
+            assert_eq!(kitty_market.listing_description(KittyId::from(1)), None);
+        }
+
+        #[ink::test]
+        fn dutch_price_declines_until_end_price() {
+            let mut kitty_market = new_market();
+            let auction = DutchAuction {
+                start_price: 1_000,
+                end_price: 400,
+                start_block: 0,
+                duration_blocks: 10,
+            };
+            kitty_market.dutch_auctions.insert(KittyId::from(1), &auction);
+
+            assert_eq!(kitty_market.current_dutch_price(KittyId::from(1)), Some(1_000));
+            advance_blocks(5);
+            assert_eq!(kitty_market.current_dutch_price(KittyId::from(1)), Some(700));
+            advance_blocks(4);
+            assert_eq!(kitty_market.current_dutch_price(KittyId::from(1)), Some(460));
+            // Expired auctions stay at the end price.
+            advance_blocks(1);
+            assert_eq!(kitty_market.current_dutch_price(KittyId::from(1)), Some(400));
+            advance_blocks(20);
+            assert_eq!(kitty_market.current_dutch_price(KittyId::from(1)), Some(400));
+            // Kitties not in an auction have no price.
+            assert_eq!(kitty_market.current_dutch_price(KittyId::from(2)), None);
+        }
+
+        #[ink::test]
+        fn invalid_dutch_auction_is_rejected() {
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.list_dutch_auction(KittyId::from(1), 0, 0, 10), Err(Error::PriceIsZero));
+            assert_eq!(
+                kitty_market.list_dutch_auction(KittyId::from(1), 100, 200, 10),
+                Err(Error::InvalidAuction)
+            );
+            list(&mut kitty_market, KittyId::from(1), 100);
+            assert_eq!(
+                kitty_market.list_dutch_auction(KittyId::from(1), 200, 100, 10),
+                Err(Error::AlreadyListedForSale)
+            );
+            assert_eq!(kitty_market.buy_dutch(KittyId::from(2)), Err(Error::NotForSale));
+        }
+
+        #[ink::test]
+        fn invalid_bids_are_rejected() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.place_bid(KittyId::from(1), 100), Err(Error::NotForSale));
+            kitty_market.auctions.insert(KittyId::from(1), &auction(accounts.alice, Some((accounts.bob, 150))));
+
+            // Bids below the reserve price or the highest bid are rejected.
+            assert_eq!(kitty_market.place_bid(KittyId::from(1), 0), Err(Error::BidTooLow));
+            assert_eq!(kitty_market.place_bid(KittyId::from(1), 150), Err(Error::BidTooLow));
+            kitty_market.auctions.insert(KittyId::from(1), &auction(accounts.alice, None));
+            assert_eq!(kitty_market.place_bid(KittyId::from(1), 99), Err(Error::BidTooLow));
+
+            // No bids are taken after the end block.
+            advance_blocks(11);
+            assert_eq!(kitty_market.place_bid(KittyId::from(1), 200), Err(Error::AuctionEnded));
+        }
+
+        #[ink::test]
+        fn settle_auction_without_bids_closes_it() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.settle_auction(KittyId::from(1)), Err(Error::NotForSale));
+            kitty_market.auctions.insert(KittyId::from(1), &auction(accounts.alice, None));
+
+            // The auction runs until its end block.
+            advance_blocks(10);
+            assert_eq!(kitty_market.settle_auction(KittyId::from(1)), Err(Error::AuctionNotEnded));
+            advance_blocks(1);
+            assert_eq!(kitty_market.settle_auction(KittyId::from(1)), Ok(()));
+            assert_eq!(kitty_market.auction(KittyId::from(1)), None);
+        }
+
+        #[ink::test]
+        fn auctioned_kitty_cannot_be_listed_again() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.list_auction(KittyId::from(1), 100, 0), Err(Error::InvalidAuction));
+            kitty_market.auctions.insert(KittyId::from(1), &auction(accounts.alice, None));
+            assert_eq!(kitty_market.list_auction(KittyId::from(1), 100, 10), Err(Error::AlreadyListedForSale));
+            assert_eq!(
+                kitty_market.list_dutch_auction(KittyId::from(1), 200, 100, 10),
+                Err(Error::AlreadyListedForSale)
+            );
+        }
+
+        #[ink::test]
+        fn mint_and_list_rejects_zero_price() {
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.mint_and_list(KittyId::from(1), 0), Err(Error::PriceIsZero));
+            assert!(!kitty_market.listed_for_sale(KittyId::from(1)));
+        }
+
+        #[ink::test]
+        fn invalid_offers_are_rejected() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.make_offer(KittyId::from(1), 0), Err(Error::PriceIsZero));
+            assert_eq!(kitty_market.cancel_offer(KittyId::from(1)), Err(Error::NoOffer));
+            assert_eq!(kitty_market.accept_offer(KittyId::from(1), accounts.bob), Err(Error::NoOffer));
+
+            // Offers can't be accepted while the market holds an auction bid.
+            kitty_market.offers.insert((KittyId::from(1), accounts.bob), &100);
+            kitty_market.auctions.insert(KittyId::from(1), &auction(accounts.alice, None));
+            assert_eq!(
+                kitty_market.accept_offer(KittyId::from(1), accounts.bob),
+                Err(Error::AlreadyListedForSale)
+            );
+            assert_eq!(kitty_market.offer(KittyId::from(1), accounts.bob), Some(100));
+        }
+
+        /// Returns an auction of `seller` with reserve price 100 ending at block 10.
+        fn auction(seller: AccountId, highest: Option<(AccountId, u128)>) -> EnglishAuction {
+            EnglishAuction {
+                seller,
+                reserve_price: 100,
+                end_block: 10,
+                highest_bidder: highest.map(|(bidder, _)| bidder),
+                highest_bid: highest.map(|(_, bid)| bid).unwrap_or_default(),
+            }
+        }
+
+        #[ink::test]
+        fn transfer_hook_delists_moved_kitty() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            list(&mut kitty_market, KittyId::from(1), 100);
+            kitty_market.kitties_for_adoption.push(KittyId::from(2));
+
+            // Only the kitties contract is listened to.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            kitty_market.on_kitty_transferred(KittyId::from(1), accounts.alice, accounts.bob);
+            assert!(kitty_market.is_for_sale(KittyId::from(1)));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(AccountId::from([0x1; 32]));
+            kitty_market.on_kitty_transferred(KittyId::from(1), accounts.alice, accounts.bob);
+            kitty_market.on_kitty_transferred(KittyId::from(2), accounts.alice, accounts.bob);
+            assert!(!kitty_market.is_for_sale(KittyId::from(1)));
+            assert!(!kitty_market.is_for_adoption(KittyId::from(2)));
+            assert_eq!(kitty_market.sale_count(), 0);
+        }
+
+        #[ink::test]
+        fn burned_kitty_is_delisted() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            list(&mut kitty_market, KittyId::from(1), 100);
+
+            // The kitties contract reports the burn as a transfer to the zero address.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(AccountId::from([0x1; 32]));
+            kitty_market.on_kitty_transferred(KittyId::from(1), accounts.alice, AccountId::from([0x0; 32]));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(kitty_market.buy(KittyId::from(1)), Err(Error::NotForSale));
+            assert_eq!(kitty_market.kitties_for_sale(), Vec::new());
+        }
+
+        #[ink::test]
+        fn reentrant_calls_are_rejected() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            list(&mut kitty_market, KittyId::from(1), 100);
+            offer(&mut kitty_market, KittyId::from(1), accounts.bob, 50);
+            // Kitty 1 is abandoned as well.
+            assert_eq!(kitty_market.set_abandonment(true, 0), Ok(()));
+            kitty_market.abandonable_since.insert(KittyId::from(1), &0);
+            advance_blocks(1);
+
+            // A guarded message is moving coins or kitties.
+            kitty_market.locked = true;
+            assert_eq!(kitty_market.buy(KittyId::from(1)), Err(Error::Reentrancy));
+            assert_eq!(kitty_market.buy_dutch(KittyId::from(1)), Err(Error::Reentrancy));
+            assert_eq!(kitty_market.adopt(KittyId::from(1)), Err(Error::Reentrancy));
+            assert_eq!(kitty_market.accept_offer(KittyId::from(1), accounts.bob), Err(Error::Reentrancy));
+            assert_eq!(kitty_market.claim_proceeds(), Err(Error::Reentrancy));
+            assert_eq!(kitty_market.reap_abandoned(ids(&[1])), 0);
+            assert!(kitty_market.is_for_sale(KittyId::from(1)));
+
+            // Failed messages release the guard again.
+            kitty_market.locked = false;
+            assert_eq!(kitty_market.buy(KittyId::from(2)), Err(Error::NotForSale));
+            assert_eq!(kitty_market.buy(KittyId::from(2)), Err(Error::NotForSale));
+        }
+
+        #[ink::test]
+        fn sale_listings_of_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            list(&mut kitty_market, KittyId::from(1), 100);
+            list(&mut kitty_market, KittyId::from(2), 200);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            list(&mut kitty_market, KittyId::from(3), 300);
+
+            assert_eq!(kitty_market.sale_listings_of(accounts.alice), vec![(KittyId::from(1), 100), (KittyId::from(2), 200)]);
+            assert_eq!(kitty_market.sale_listings_of(accounts.bob), vec![(KittyId::from(3), 300)]);
+            assert_eq!(kitty_market.sale_listings_of(accounts.charlie), Vec::new());
+
+            // A delisted kitty leaves the listings of its seller.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(AccountId::from([0x1; 32]));
+            kitty_market.on_kitty_transferred(KittyId::from(1), accounts.alice, accounts.bob);
+            assert_eq!(kitty_market.sale_listings_of(accounts.alice), vec![(KittyId::from(2), 200)]);
+        }
+
+        #[ink::test]
+        fn activity_counters_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.sales_by_seller(accounts.alice), 0);
+            assert_eq!(kitty_market.adoptions_by_lister(accounts.alice), 0);
+
+            kitty_market.record_sale(accounts.alice);
+            kitty_market.record_sale(accounts.alice);
+            kitty_market.record_adoption(accounts.bob);
+
+            assert_eq!(kitty_market.sales_by_seller(accounts.alice), 2);
+            assert_eq!(kitty_market.sales_by_seller(accounts.bob), 0);
+            assert_eq!(kitty_market.adoptions_by_lister(accounts.bob), 1);
+            assert_eq!(kitty_market.adoptions_by_lister(accounts.alice), 0);
+        }
+
+        #[ink::test]
+        fn market_announces_its_configuration() {
+            let kitty_market = KittyMarket::new(
+                AccountId::from([0x1; 32]),
+                AccountId::from([0x2; 32]),
+                20_000,
+                AccountId::from([0x3; 32]),
+                5,
+                Some(0),
+            );
+            assert_eq!(kitty_market.kitties_contract(), AccountId::from([0x1; 32]));
+            assert_eq!(kitty_market.coin_contract(), AccountId::from([0x2; 32]));
+
+            let emitted_events = ink::env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 1);
+            let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[0].data[..])
+                .expect("decoded error");
+            match decoded {
+                Event::MarketInitialized(MarketInitialized {
+                    kitties,
+                    kitty_coin,
+                    fee_bps,
+                    fee_recipient,
+                    min_price,
+                    max_price,
+                }) => {
+                    assert_eq!(kitties, AccountId::from([0x1; 32]));
+                    assert_eq!(kitty_coin, AccountId::from([0x2; 32]));
+                    // The fee is capped.
+                    assert_eq!(fee_bps, 10_000);
+                    assert_eq!(fee_recipient, AccountId::from([0x3; 32]));
+                    // A zero maximum price is unbounded.
+                    assert_eq!(min_price, 5);
+                    assert_eq!(max_price, None);
+                }
+                _ => panic!("MarketInitialized event not emitted"),
+            }
+        }
+
+        #[ink::test]
+        fn adoption_fee_is_dropped_with_listing() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            kitty_market.kitties_for_adoption.push(KittyId::from(1));
+            kitty_market.adoption_fees.insert(KittyId::from(1), &100);
+            assert_eq!(kitty_market.adoption_fee(KittyId::from(1)), 100);
+            assert_eq!(kitty_market.adoption_fee(KittyId::from(2)), 0);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(AccountId::from([0x1; 32]));
+            kitty_market.on_kitty_transferred(KittyId::from(1), accounts.alice, accounts.bob);
+            assert!(!kitty_market.is_for_adoption(KittyId::from(1)));
+            assert_eq!(kitty_market.adoption_fee(KittyId::from(1)), 0);
+        }
+
+        #[ink::test]
+        fn rescue_requires_market_owner() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(kitty_market.rescue_kitty(KittyId::from(1), accounts.bob), Err(Error::NotMarketOwner));
+            assert_eq!(kitty_market.rescue_coin(1, accounts.bob), Err(Error::NotMarketOwner));
+        }
+
+        #[ink::test]
+        fn set_sale_hook_requires_market_owner() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut kitty_market = new_market();
+            assert_eq!(kitty_market.set_sale_hook(Some(accounts.django)), Ok(()));
+            assert_eq!(kitty_market.sale_hook(), Some(accounts.django));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(kitty_market.set_sale_hook(None), Err(Error::NotMarketOwner));
+            assert_eq!(kitty_market.sale_hook(), Some(accounts.django));
+        }
+
+        #[ink::test]
+        fn zero_min_lister_balance_is_disabled() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let kitty_market = new_market();
+            // No balance is queried while the check is disabled.
+            assert_eq!(kitty_market.ensure_lister_balance(accounts.bob), Ok(()));
+        }
+
+        fn advance_blocks(blocks: u32) {
+            for _ in 0..blocks {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            }
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink_e2e::{build_message, AccountKeyring};
+        use kitties::KittiesRef;
+        use kitty_coin::KittyCoinRef;
+        use sale_hook_mock::SaleHookMockRef;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        const MINT_PRICE: u128 = 10;
+
+        /// Instantiates KittyCoin, Kitties and a market paying `fee_bps` to Charlie, and lets
+        /// Alice mint kitty 1. Returns the kitty coin, kitties and market accounts.
+        async fn setup(
+            client: &mut ink_e2e::Client<ink_e2e::PolkadotConfig, ink::env::DefaultEnvironment>,
+            fee_bps: u16,
+        ) -> (AccountId, AccountId, AccountId) {
+            let alice_acc = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+
+            let coin_constructor = KittyCoinRef::new(10_000);
+            let coin_account_id = client
+                .instantiate("kitty_coin", &ink_e2e::alice(), coin_constructor, 0, None)
+                .await
+                .expect("instantiate kitty_coin failed")
+                .account_id;
+
+            let kitties_constructor =
+                KittiesRef::new(coin_account_id, MINT_PRICE, None, alice_acc, 0, false);
+            let kitties_account_id = client
+                .instantiate("kitties", &ink_e2e::alice(), kitties_constructor, 0, None)
+                .await
+                .expect("instantiate kitties failed")
+                .account_id;
+
+            let charlie_acc = ink_e2e::account_id(ink_e2e::AccountKeyring::Charlie);
+            let constructor = KittyMarketRef::new(
+                kitties_account_id,
+                coin_account_id,
+                fee_bps,
+                charlie_acc,
+                0,
+                None,
+            );
+            let market_account_id = client
+                .instantiate("kitty_market", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate kitty_market failed")
+                .account_id;
+
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(kitties_account_id, MINT_PRICE));
+            client
+                .call(&ink_e2e::alice(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+
+            let mint_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.mint(KittyId::from(1)));
+            client
+                .call(&ink_e2e::alice(), mint_msg, 0, None)
+                .await
+                .expect("mint failed");
+
+            (coin_account_id, kitties_account_id, market_account_id)
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn min_lister_balance_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (_, kitties_account_id, market_account_id) = setup(&mut client, 0).await;
+
+            // Alice holds 10_000 - MINT_PRICE coins, less than required.
+            let set_min_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.set_min_lister_balance(10_000));
+            client
+                .call(&ink_e2e::alice(), set_min_msg, 0, None)
+                .await
+                .expect("set_min_lister_balance failed");
+
+            let list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_sale(KittyId::from(1), 100, None));
+            let res = client.call_dry_run(&ink_e2e::alice(), &list_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::InsufficientListerBalance));
+            let list_adoption_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_adoption(KittyId::from(1), None));
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &list_adoption_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), Err(Error::InsufficientListerBalance));
+
+            // With a lower requirement Alice can list her approved kitty.
+            let set_min_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.set_min_lister_balance(1_000));
+            client
+                .call(&ink_e2e::alice(), set_min_msg, 0, None)
+                .await
+                .expect("set_min_lister_balance failed");
+
+            approve_market(
+                &mut client,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+            let res = client.call_dry_run(&ink_e2e::alice(), &list_msg, 0, None).await;
+            assert_eq!(res.return_value(), Ok(()));
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn listing_requires_market_approval(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let (_, kitties_account_id, market_account_id) = setup(&mut client, 0).await;
+
+            // Alice did not approve the market yet.
+            let list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_sale(KittyId::from(1), 100, None));
+            let res = client.call_dry_run(&ink_e2e::alice(), &list_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::ListSaleNotApproved));
+            let list_adoption_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_adoption(KittyId::from(1), None));
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &list_adoption_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), Err(Error::ListAdoptNotApproved));
+
+            // Once approved the market lists the kitty.
+            approve_market(
+                &mut client,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+            client
+                .call(&ink_e2e::alice(), list_msg, 0, None)
+                .await
+                .expect("list_for_sale failed");
+
+            let kitties_for_sale_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.kitties_for_sale());
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &kitties_for_sale_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), vec![(KittyId::from(1), 100)]);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn free_adoption_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+            approve_market(
+                &mut client,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            let list_adoption_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_adoption(KittyId::from(1), None));
+            client
+                .call(&ink_e2e::alice(), list_adoption_msg, 0, None)
+                .await
+                .expect("list_for_adoption failed");
+
+            // Bob adopts the kitty without any coins.
+            let adopt_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.adopt(KittyId::from(1)));
+            client
+                .call(&ink_e2e::bob(), adopt_msg, 0, None)
+                .await
+                .expect("adopt failed");
+
+            let owner_of_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.owner_of(KittyId::from(1)));
+            let owner = client
+                .call_dry_run(&ink_e2e::bob(), &owner_of_msg, 0, None)
+                .await;
+            assert_eq!(owner.return_value(), Some(ink_e2e::account_id(AccountKeyring::Bob)));
+            assert_eq!(balance_of(&mut client, coin_account_id, AccountKeyring::Bob).await, 0);
+
+            let alice_acc = ink_e2e::account_id(AccountKeyring::Alice);
+            let adoptions_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.adoptions_by_lister(alice_acc));
+            let res = client.call_dry_run(&ink_e2e::alice(), &adoptions_msg, 0, None).await;
+            assert_eq!(res.return_value(), 1);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn paid_adoption_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+            approve_market(
+                &mut client,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            let list_adoption_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_adoption(KittyId::from(1), Some(100)));
+            client
+                .call(&ink_e2e::alice(), list_adoption_msg, 0, None)
+                .await
+                .expect("list_for_adoption failed");
+
+            // Bob can't pay the fee yet.
+            let adopt_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.adopt(KittyId::from(1)));
+            let res = client.call_dry_run(&ink_e2e::bob(), &adopt_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::CoinTransferFail));
+
+            let bob_acc = ink_e2e::account_id(AccountKeyring::Bob);
+            let transfer_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.transfer(bob_acc, 100));
+            client
+                .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                .await
+                .expect("transfer failed");
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(market_account_id, 100));
+            client
+                .call(&ink_e2e::bob(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+            client
+                .call(&ink_e2e::bob(), adopt_msg, 0, None)
+                .await
+                .expect("adopt failed");
+
+            // Alice got the fee back from Bob.
+            assert_eq!(
+                balance_of(&mut client, coin_account_id, AccountKeyring::Alice).await,
+                10_000 - MINT_PRICE
+            );
+            assert_eq!(balance_of(&mut client, coin_account_id, AccountKeyring::Bob).await, 0);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn rescue_spares_escrow(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+            let alice_acc = ink_e2e::account_id(AccountKeyring::Alice);
+            let bob_acc = ink_e2e::account_id(AccountKeyring::Bob);
+
+            // Alice sends coins and her kitty to the market by mistake.
+            let transfer_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.transfer(market_account_id, 500));
+            client
+                .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                .await
+                .expect("transfer failed");
+            let transfer_kitty_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.transfer(market_account_id, KittyId::from(1)));
+            client
+                .call(&ink_e2e::alice(), transfer_kitty_msg, 0, None)
+                .await
+                .expect("kitty transfer failed");
+
+            // Bob escrows an offer with the market.
+            let transfer_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.transfer(bob_acc, 300));
+            client
+                .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                .await
+                .expect("transfer failed");
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(market_account_id, 300));
+            client
+                .call(&ink_e2e::bob(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+            let offer_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.make_offer(KittyId::from(1), 300));
+            client
+                .call(&ink_e2e::bob(), offer_msg, 0, None)
+                .await
+                .expect("make_offer failed");
+
+            // The offer can't be rescued, the stray coins can.
+            let rescue_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.rescue_coin(501, alice_acc));
+            let res = client.call_dry_run(&ink_e2e::alice(), &rescue_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::NotRescuable));
+            let rescue_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.rescue_coin(500, alice_acc));
+            client
+                .call(&ink_e2e::alice(), rescue_msg, 0, None)
+                .await
+                .expect("rescue_coin failed");
+            assert_eq!(
+                balance_of(&mut client, coin_account_id, AccountKeyring::Alice).await,
+                10_000 - MINT_PRICE - 300
+            );
+
+            let rescue_kitty_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.rescue_kitty(KittyId::from(1), alice_acc));
+            client
+                .call(&ink_e2e::alice(), rescue_kitty_msg, 0, None)
+                .await
+                .expect("rescue_kitty failed");
+            let owner_of_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.owner_of(KittyId::from(1)));
+            let owner = client
+                .call_dry_run(&ink_e2e::alice(), &owner_of_msg, 0, None)
+                .await;
+            assert_eq!(owner.return_value(), Some(alice_acc));
+
+            // Kitties the market does not own can't be rescued.
+            let rescue_kitty_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.rescue_kitty(KittyId::from(1), bob_acc));
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &rescue_kitty_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), Err(Error::NotRescuable));
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn operator_market_can_list(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (_, kitties_account_id, market_account_id) = setup(&mut client, 0).await;
+
+            // Alice approves the market for all her kitties.
+            let approve_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.set_approval_for_all(market_account_id, true));
+            client
+                .call(&ink_e2e::alice(), approve_msg, 0, None)
+                .await
+                .expect("set_approval_for_all failed");
+
+            let list_adoption_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_adoption(KittyId::from(1), None));
+            client
+                .call(&ink_e2e::alice(), list_adoption_msg, 0, None)
+                .await
+                .expect("list_for_adoption failed");
+
+            let adoption_list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.adoption_list());
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &adoption_list_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), vec![KittyId::from(1)]);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn expired_listing_is_swept(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (_, kitties_account_id, market_account_id) = setup(&mut client, 0).await;
+            approve_market(
+                &mut client,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            // Alice lists kitty 1 until block 1, which has already passed.
+            let list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_sale(KittyId::from(1), 100, Some(1)));
+            client
+                .call(&ink_e2e::alice(), list_msg, 0, None)
+                .await
+                .expect("list_for_sale failed");
+
+            let buy_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.buy(KittyId::from(1)));
+            let res = client.call_dry_run(&ink_e2e::bob(), &buy_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::ListingExpired));
+
+            let sweep_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.sweep_expired(vec![KittyId::from(1)]));
+            client
+                .call(&ink_e2e::bob(), sweep_msg, 0, None)
+                .await
+                .expect("sweep_expired failed");
+
+            let kitties_for_sale_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.kitties_for_sale());
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &kitties_for_sale_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), vec![]);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn mint_and_list_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+            let alice_acc = ink_e2e::account_id(AccountKeyring::Alice);
+            allow_minting(&mut client, coin_account_id, kitties_account_id, market_account_id)
+                .await;
+
+            let mint_and_list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.mint_and_list(KittyId::from(2), 500));
+            client
+                .call(&ink_e2e::alice(), mint_and_list_msg, 0, None)
+                .await
+                .expect("mint_and_list failed");
+
+            let owner_of_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.owner_of(KittyId::from(2)));
+            let owner = client
+                .call_dry_run(&ink_e2e::alice(), &owner_of_msg, 0, None)
+                .await;
+            assert_eq!(owner.return_value(), Some(alice_acc));
+
+            let kitties_for_sale_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.kitties_for_sale());
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &kitties_for_sale_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), vec![(KittyId::from(2), 500)]);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn failed_listing_rolls_back_mint(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+            allow_minting(&mut client, coin_account_id, kitties_account_id, market_account_id)
+                .await;
+
+            // Alice holds 10_000 - MINT_PRICE coins, paying for another kitty leaves her
+            // below the required balance.
+            let set_min_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.set_min_lister_balance(9_985));
+            client
+                .call(&ink_e2e::alice(), set_min_msg, 0, None)
+                .await
+                .expect("set_min_lister_balance failed");
+
+            let mint_and_list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.mint_and_list(KittyId::from(2), 500));
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &mint_and_list_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), Err(Error::InsufficientListerBalance));
+            let res = client.call(&ink_e2e::alice(), mint_and_list_msg, 0, None).await;
+            assert!(res.is_err());
+
+            // Neither the kitty nor its payment survived.
+            let owner_of_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.owner_of(KittyId::from(2)));
+            let owner = client
+                .call_dry_run(&ink_e2e::alice(), &owner_of_msg, 0, None)
+                .await;
+            assert_eq!(owner.return_value(), None);
+            assert_eq!(
+                balance_of(&mut client, coin_account_id, AccountKeyring::Alice).await,
+                10_000 - MINT_PRICE
+            );
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn abandoned_kitty_is_burned(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (_, kitties_account_id, market_account_id) = setup(&mut client, 0).await;
+            approve_market(
+                &mut client,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            let set_market_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.set_market(Some(market_account_id)));
+            client
+                .call(&ink_e2e::alice(), set_market_msg, 0, None)
+                .await
+                .expect("set_market failed");
+
+            let abandonment_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.set_abandonment(true, 0));
+            client
+                .call(&ink_e2e::alice(), abandonment_msg, 0, None)
+                .await
+                .expect("set_abandonment failed");
+
+            let list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_sale_abandonable(KittyId::from(1), 100));
+            client
+                .call(&ink_e2e::alice(), list_msg, 0, None)
+                .await
+                .expect("list_for_sale_abandonable failed");
+
+            // The listing has been inactive since an earlier block.
+            let reap_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.reap_abandoned(vec![KittyId::from(1)]));
+            let res = client.call_dry_run(&ink_e2e::bob(), &reap_msg, 0, None).await;
+            assert_eq!(res.return_value(), 1);
+            client
+                .call(&ink_e2e::bob(), reap_msg, 0, None)
+                .await
+                .expect("reap_abandoned failed");
+
+            let owner_of_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.owner_of(KittyId::from(1)));
+            let owner = client
+                .call_dry_run(&ink_e2e::alice(), &owner_of_msg, 0, None)
+                .await;
+            assert_eq!(owner.return_value(), None);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn revoked_listing_is_reaped(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (_, kitties_account_id, market_account_id) = setup(&mut client, 0).await;
+
+            let approve_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.set_approval_for_all(market_account_id, true));
+            client
+                .call(&ink_e2e::alice(), approve_msg, 0, None)
+                .await
+                .expect("set_approval_for_all failed");
+
+            let list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_sale(KittyId::from(1), 100, None));
+            client
+                .call(&ink_e2e::alice(), list_msg, 0, None)
+                .await
+                .expect("list_for_sale failed");
+
+            // A healthy listing is kept.
+            let reap_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.reap_unhealthy(vec![KittyId::from(1)]));
+            let res = client.call_dry_run(&ink_e2e::bob(), &reap_msg, 0, None).await;
+            assert_eq!(res.return_value(), 0);
+
+            // Alice revokes the market's approval.
+            let revoke_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.set_approval_for_all(market_account_id, false));
+            client
+                .call(&ink_e2e::alice(), revoke_msg, 0, None)
+                .await
+                .expect("set_approval_for_all failed");
+
+            let health_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.listing_health(KittyId::from(1)));
+            let res = client.call_dry_run(&ink_e2e::bob(), &health_msg, 0, None).await;
+            assert_eq!(
+                res.return_value(),
+                ListingHealth {
+                    owner_ok: true,
+                    approval_ok: false
+                }
+            );
+
+            let res = client.call_dry_run(&ink_e2e::bob(), &reap_msg, 0, None).await;
+            assert_eq!(res.return_value(), 1);
+            client
+                .call(&ink_e2e::bob(), reap_msg, 0, None)
+                .await
+                .expect("reap_unhealthy failed");
+
+            let kitties_for_sale_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.kitties_for_sale());
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &kitties_for_sale_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), vec![]);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn unlisting_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (_, kitties_account_id, market_account_id) = setup(&mut client, 0).await;
+            approve_market(
+                &mut client,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            // Alice lists kitty 1 for sale, then cancels the listing.
+            let list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_sale(KittyId::from(1), 100, None));
+            client
+                .call(&ink_e2e::alice(), list_msg, 0, None)
+                .await
+                .expect("list_for_sale failed");
+
+            // Bob doesn't own the kitty.
+            let unlist_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.unlist_from_sale(KittyId::from(1)));
+            let res = client.call_dry_run(&ink_e2e::bob(), &unlist_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::NotOwner));
+
+            client
+                .call(&ink_e2e::alice(), unlist_msg, 0, None)
+                .await
+                .expect("unlist_from_sale failed");
+            let kitties_for_sale_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.kitties_for_sale());
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &kitties_for_sale_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), vec![]);
+
+            // The same for adoption.
+            let list_adoption_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_adoption(KittyId::from(1), None));
+            client
+                .call(&ink_e2e::alice(), list_adoption_msg, 0, None)
+                .await
+                .expect("list_for_adoption failed");
+            let unlist_adoption_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.unlist_from_adoption(KittyId::from(1)));
+            client
+                .call(&ink_e2e::alice(), unlist_adoption_msg, 0, None)
+                .await
+                .expect("unlist_from_adoption failed");
+            let adoption_list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.adoption_list());
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &adoption_list_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), vec![]);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn update_sale_price_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (_, kitties_account_id, market_account_id) = setup(&mut client, 0).await;
+            approve_market(
+                &mut client,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            let list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_sale(KittyId::from(1), 100, None));
+            client
+                .call(&ink_e2e::alice(), list_msg, 0, None)
+                .await
+                .expect("list_for_sale failed");
+
+            // Bob doesn't own the kitty.
+            let update_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.update_sale_price(KittyId::from(1), 60));
+            let res = client.call_dry_run(&ink_e2e::bob(), &update_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::NotOwner));
+
+            // Alice drops her price.
+            client
+                .call(&ink_e2e::alice(), update_msg, 0, None)
+                .await
+                .expect("update_sale_price failed");
+            let kitties_for_sale_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.kitties_for_sale());
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &kitties_for_sale_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), vec![(KittyId::from(1), 60)]);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn buy_splits_fee(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 250).await;
+            buy_kitty(
+                &mut client,
+                coin_account_id,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            // Charlie gets 2.5% of the price, Alice the rest.
+            assert_eq!(balance_of(&mut client, coin_account_id, AccountKeyring::Charlie).await, 25);
+            assert_eq!(
+                balance_of(&mut client, coin_account_id, AccountKeyring::Alice).await,
+                10_000 - MINT_PRICE - 1_000 + 975
+            );
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn buy_pays_royalty(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 250).await;
+
+            // Dave gets a 5% royalty on kitty sales.
+            let dave_acc = ink_e2e::account_id(AccountKeyring::Dave);
+            let royalty_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.set_default_royalty(dave_acc, 500));
+            client
+                .call(&ink_e2e::alice(), royalty_msg, 0, None)
+                .await
+                .expect("set_default_royalty failed");
+
+            buy_kitty(
+                &mut client,
+                coin_account_id,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            // Charlie gets 2.5% of the price, Dave 5% and Alice the rest.
+            assert_eq!(balance_of(&mut client, coin_account_id, AccountKeyring::Charlie).await, 25);
+            assert_eq!(balance_of(&mut client, coin_account_id, AccountKeyring::Dave).await, 50);
+            assert_eq!(
+                balance_of(&mut client, coin_account_id, AccountKeyring::Alice).await,
+                10_000 - MINT_PRICE - 1_000 + 925
+            );
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn buy_without_fee_pays_seller_in_full(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+            buy_kitty(
+                &mut client,
+                coin_account_id,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            assert_eq!(balance_of(&mut client, coin_account_id, AccountKeyring::Charlie).await, 0);
+            assert_eq!(
+                balance_of(&mut client, coin_account_id, AccountKeyring::Alice).await,
+                10_000 - MINT_PRICE
+            );
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn unapproved_buyer_is_rejected(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+            approve_market(
+                &mut client,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            let list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_sale(KittyId::from(1), 1_000, None));
+            client
+                .call(&ink_e2e::alice(), list_msg, 0, None)
+                .await
+                .expect("list_for_sale failed");
+
+            // Bob can afford the kitty but approved the market for less than its price.
+            let bob_acc = ink_e2e::account_id(AccountKeyring::Bob);
+            let transfer_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.transfer(bob_acc, 1_000));
+            client
+                .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                .await
+                .expect("transfer failed");
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(market_account_id, 999));
+            client
+                .call(&ink_e2e::bob(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+
+            let buy_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.buy(KittyId::from(1)));
+            let res = client.call_dry_run(&ink_e2e::bob(), &buy_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::InsufficientAllowance));
+            assert_eq!(
+                balance_of(&mut client, coin_account_id, AccountKeyring::Bob).await,
+                1_000
+            );
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn buy_failures_are_explained(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+
+            let approve_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.set_approval_for_all(market_account_id, true));
+            client
+                .call(&ink_e2e::alice(), approve_msg, 0, None)
+                .await
+                .expect("set_approval_for_all failed");
+            let list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_sale(KittyId::from(1), 1_000, None));
+            client
+                .call(&ink_e2e::alice(), list_msg, 0, None)
+                .await
+                .expect("list_for_sale failed");
+
+            // Bob approved the market but holds no coins.
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(market_account_id, 1_000));
+            client
+                .call(&ink_e2e::bob(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+            let buy_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.buy(KittyId::from(1)));
+            let res = client.call_dry_run(&ink_e2e::bob(), &buy_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::InsufficientBalance));
+
+            // Bob can pay, but Alice revoked the market's approval.
+            let bob_acc = ink_e2e::account_id(AccountKeyring::Bob);
+            let transfer_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.transfer(bob_acc, 1_000));
+            client
+                .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                .await
+                .expect("transfer failed");
+            let revoke_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.set_approval_for_all(market_account_id, false));
+            client
+                .call(&ink_e2e::alice(), revoke_msg, 0, None)
+                .await
+                .expect("set_approval_for_all failed");
+            let res = client.call_dry_run(&ink_e2e::bob(), &buy_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::TransferNotApproved));
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn listing_description_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+            approve_market(
+                &mut client,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            let list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| {
+                    kitty_market.list_for_sale_with_description(
+                        KittyId::from(1),
+                        1_000,
+                        b"fluffy".to_vec(),
+                    )
+                });
+            client
+                .call(&ink_e2e::alice(), list_msg, 0, None)
+                .await
+                .expect("list_for_sale_with_description failed");
+
+            let description_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.listing_description(KittyId::from(1)));
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &description_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), Some(b"fluffy".to_vec()));
+
+            // Bob buys the kitty, which clears the description.
+            let bob_acc = ink_e2e::account_id(AccountKeyring::Bob);
+            let transfer_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.transfer(bob_acc, 1_000));
+            client
+                .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                .await
+                .expect("transfer failed");
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(market_account_id, 1_000));
+            client
+                .call(&ink_e2e::bob(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+            let buy_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.buy(KittyId::from(1)));
+            client
+                .call(&ink_e2e::bob(), buy_msg, 0, None)
+                .await
+                .expect("buy failed");
+
+            let res = client
+                .call_dry_run(&ink_e2e::alice(), &description_msg, 0, None)
+                .await;
+            assert_eq!(res.return_value(), None);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn buy_dutch_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+            approve_market(
+                &mut client,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+            let bob_acc = ink_e2e::account_id(AccountKeyring::Bob);
+
+            // The auction ends before Bob buys, so he pays the end price.
+            let list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| {
+                    kitty_market.list_dutch_auction(KittyId::from(1), 1_000, 400, 1)
+                });
+            client
+                .call(&ink_e2e::alice(), list_msg, 0, None)
+                .await
+                .expect("list_dutch_auction failed");
+
+            let transfer_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.transfer(bob_acc, 1_000));
+            client
+                .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                .await
+                .expect("transfer failed");
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(market_account_id, 1_000));
+            client
+                .call(&ink_e2e::bob(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+
+            let buy_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.buy_dutch(KittyId::from(1)));
+            client
+                .call(&ink_e2e::bob(), buy_msg, 0, None)
+                .await
+                .expect("buy_dutch failed");
+
+            assert_eq!(balance_of(&mut client, coin_account_id, AccountKeyring::Bob).await, 600);
+            let owner_of_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.owner_of(KittyId::from(1)));
+            let owner = client
+                .call_dry_run(&ink_e2e::bob(), &owner_of_msg, 0, None)
+                .await;
+            assert_eq!(owner.return_value(), Some(bob_acc));
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn outbid_bidder_is_refunded(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+            approve_market(
+                &mut client,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            let list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| {
+                    kitty_market.list_auction(KittyId::from(1), 100, BlockNumber::MAX)
+                });
+            client
+                .call(&ink_e2e::alice(), list_msg, 0, None)
+                .await
+                .expect("list_auction failed");
+
+            // Alice funds Bob and Charlie, who both let the market take their bids.
+            for (keyring, signer) in [
+                (AccountKeyring::Bob, ink_e2e::bob()),
+                (AccountKeyring::Charlie, ink_e2e::charlie()),
+            ] {
+                let account = ink_e2e::account_id(keyring);
+                let transfer_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                    .call(|kitty_coin| kitty_coin.transfer(account, 1_000));
+                client
+                    .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                    .await
+                    .expect("transfer failed");
+                let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                    .call(|kitty_coin| kitty_coin.approve(market_account_id, 1_000));
+                client
+                    .call(&signer, approve_msg, 0, None)
+                    .await
+                    .expect("approve failed");
+            }
+
+            // Bob's bid is held by the market.
+            let bid_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.place_bid(KittyId::from(1), 200));
+            client
+                .call(&ink_e2e::bob(), bid_msg, 0, None)
+                .await
+                .expect("place_bid failed");
+            assert_eq!(balance_of(&mut client, coin_account_id, AccountKeyring::Bob).await, 800);
+
+            // Charlie has to outbid Bob.
+            let low_bid_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.place_bid(KittyId::from(1), 200));
+            let res = client.call_dry_run(&ink_e2e::charlie(), &low_bid_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::BidTooLow));
+            let bid_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.place_bid(KittyId::from(1), 300));
+            client
+                .call(&ink_e2e::charlie(), bid_msg, 0, None)
+                .await
+                .expect("place_bid failed");
+
+            // Bob got his bid back, the market holds Charlie's.
+            assert_eq!(balance_of(&mut client, coin_account_id, AccountKeyring::Bob).await, 1_000);
+            assert_eq!(
+                balance_of(&mut client, coin_account_id, AccountKeyring::Charlie).await,
+                700
+            );
+            let auction_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.auction(KittyId::from(1)));
+            let auction = client
+                .call_dry_run(&ink_e2e::alice(), &auction_msg, 0, None)
+                .await
+                .return_value()
+                .expect("auction exists");
+            let charlie_acc = ink_e2e::account_id(AccountKeyring::Charlie);
+            assert_eq!(auction.highest_bidder, Some(charlie_acc));
+            assert_eq!(auction.highest_bid, 300);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn unsettleable_auction_refunds_winner(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+            approve_market(
+                &mut client,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            // Dry runs don't produce blocks, so probe for the first block the auction can end
+            // at, and leave room for the five calls before the auction should end.
+            let mut end_block = 1;
+            loop {
+                let probe_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                    .call(|kitty_market| {
+                        kitty_market.list_auction(KittyId::from(1), 100, end_block)
+                    });
+                let res = client.call_dry_run(&ink_e2e::alice(), &probe_msg, 0, None).await;
+                if res.return_value() != Err(Error::InvalidAuction) {
+                    break;
+                }
+                end_block += 1;
+            }
+            end_block += 5;
+
+            let list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| {
+                    kitty_market.list_auction(KittyId::from(1), 100, end_block)
+                });
+            client
+                .call(&ink_e2e::alice(), list_msg, 0, None)
+                .await
+                .expect("list_auction failed");
+
+            // Alice funds Bob, who bids on kitty 1.
+            let bob_acc = ink_e2e::account_id(AccountKeyring::Bob);
+            let transfer_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.transfer(bob_acc, 1_000));
+            client
+                .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                .await
+                .expect("transfer failed");
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(market_account_id, 1_000));
+            client
+                .call(&ink_e2e::bob(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+            let bid_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.place_bid(KittyId::from(1), 200));
+            client
+                .call(&ink_e2e::bob(), bid_msg, 0, None)
+                .await
+                .expect("place_bid failed");
+            assert_eq!(balance_of(&mut client, coin_account_id, AccountKeyring::Bob).await, 800);
+
+            // Alice takes the market's approval back before the auction ends.
+            let zero_acc = AccountId::from([0x0; 32]);
+            let revoke_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.approve(zero_acc, KittyId::from(1)));
+            client
+                .call(&ink_e2e::alice(), revoke_msg, 0, None)
+                .await
+                .expect("approve failed");
+
+            // Every call produces a block, so keep calling until the auction has ended.
+            let settle_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.settle_auction(KittyId::from(1)));
+            while client
+                .call_dry_run(&ink_e2e::bob(), &settle_msg, 0, None)
+                .await
+                .return_value()
+                == Err(Error::AuctionNotEnded)
+            {
+                let tick_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                    .call(|kitty_coin| kitty_coin.approve(market_account_id, 0));
+                client
+                    .call(&ink_e2e::alice(), tick_msg, 0, None)
+                    .await
+                    .expect("approve failed");
+            }
+            client
+                .call(&ink_e2e::bob(), settle_msg, 0, None)
+                .await
+                .expect("settle_auction failed");
+
+            // The auction is closed, Alice keeps her kitty and Bob gets his bid back.
+            let auction_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.auction(KittyId::from(1)));
+            let res = client.call_dry_run(&ink_e2e::alice(), &auction_msg, 0, None).await;
+            assert_eq!(res.return_value(), None);
+            let owner_of_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.owner_of(KittyId::from(1)));
+            let res = client.call_dry_run(&ink_e2e::alice(), &owner_of_msg, 0, None).await;
+            assert_eq!(res.return_value(), Some(ink_e2e::account_id(AccountKeyring::Alice)));
+            assert_eq!(balance_of(&mut client, coin_account_id, AccountKeyring::Bob).await, 1_000);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn offers_work(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+            approve_market(
+                &mut client,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+            let bob_acc = ink_e2e::account_id(AccountKeyring::Bob);
+
+            // Bob and Charlie both make offers for Alice's unlisted kitty.
+            for (keyring, signer, amount) in [
+                (AccountKeyring::Bob, ink_e2e::bob(), 300),
+                (AccountKeyring::Charlie, ink_e2e::charlie(), 200),
+            ] {
+                let account = ink_e2e::account_id(keyring);
+                let transfer_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                    .call(|kitty_coin| kitty_coin.transfer(account, 1_000));
+                client
+                    .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                    .await
+                    .expect("transfer failed");
+                let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                    .call(|kitty_coin| kitty_coin.approve(market_account_id, amount));
+                client
+                    .call(&signer, approve_msg, 0, None)
+                    .await
+                    .expect("approve failed");
+                let offer_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                    .call(|kitty_market| kitty_market.make_offer(KittyId::from(1), amount));
+                client
+                    .call(&signer, offer_msg, 0, None)
+                    .await
+                    .expect("make_offer failed");
+            }
+            assert_eq!(balance_of(&mut client, coin_account_id, AccountKeyring::Bob).await, 700);
+
+            // Only the owner can accept an offer.
+            let accept_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.accept_offer(KittyId::from(1), bob_acc));
+            let res = client.call_dry_run(&ink_e2e::charlie(), &accept_msg, 0, None).await;
+            assert_eq!(res.return_value(), Err(Error::NotOwner));
+
+            // Alice accepts Bob's offer.
+            client
+                .call(&ink_e2e::alice(), accept_msg, 0, None)
+                .await
+                .expect("accept_offer failed");
+            let owner_of_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.owner_of(KittyId::from(1)));
+            let owner = client
+                .call_dry_run(&ink_e2e::alice(), &owner_of_msg, 0, None)
+                .await;
+            assert_eq!(owner.return_value(), Some(bob_acc));
+            assert_eq!(
+                balance_of(&mut client, coin_account_id, AccountKeyring::Alice).await,
+                10_000 - MINT_PRICE - 2_000 + 300
+            );
+
+            // Charlie's offer is still open and he can cancel it.
+            let cancel_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.cancel_offer(KittyId::from(1)));
+            client
+                .call(&ink_e2e::charlie(), cancel_msg, 0, None)
+                .await
+                .expect("cancel_offer failed");
+            assert_eq!(
+                balance_of(&mut client, coin_account_id, AccountKeyring::Charlie).await,
+                1_000
+            );
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml"
+        )]
+        async fn sales_are_counted(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+            let alice_acc = ink_e2e::account_id(AccountKeyring::Alice);
+
+            buy_kitty(
+                &mut client,
+                coin_account_id,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            // Alice mints and sells a second kitty.
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(kitties_account_id, MINT_PRICE));
+            client
+                .call(&ink_e2e::alice(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+            let mint_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.mint(KittyId::from(2)));
+            client
+                .call(&ink_e2e::alice(), mint_msg, 0, None)
+                .await
+                .expect("mint failed");
+            buy_kitty(
+                &mut client,
+                coin_account_id,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(2),
+            )
+            .await;
+
+            let sales_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.sales_by_seller(alice_acc));
+            let res = client.call_dry_run(&ink_e2e::alice(), &sales_msg, 0, None).await;
+            assert_eq!(res.return_value(), 2);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml ../sale_hook_mock/Cargo.toml"
+        )]
+        async fn sale_hook_is_notified(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+            let hook_account_id =
+                set_sale_hook(&mut client, market_account_id, false).await;
+
+            buy_kitty(
+                &mut client,
+                coin_account_id,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            let last_sale_msg = build_message::<SaleHookMockRef>(hook_account_id.clone())
+                .call(|sale_hook| sale_hook.last_sale());
+            let last_sale = client
+                .call_dry_run(&ink_e2e::alice(), &last_sale_msg, 0, None)
+                .await;
+            assert_eq!(
+                last_sale.return_value(),
+                Some((
+                    KittyId::from(1),
+                    ink_e2e::account_id(AccountKeyring::Alice),
+                    ink_e2e::account_id(AccountKeyring::Bob),
+                    1_000
+                ))
+            );
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(
+            additional_contracts = "../kitty_coin/Cargo.toml ../kitties/Cargo.toml ../sale_hook_mock/Cargo.toml"
+        )]
+        async fn reverting_sale_hook_does_not_block_sale(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let (coin_account_id, kitties_account_id, market_account_id) =
+                setup(&mut client, 0).await;
+            set_sale_hook(&mut client, market_account_id, true).await;
+
+            // The sale goes through even though the hook reverts.
+            buy_kitty(
+                &mut client,
+                coin_account_id,
+                kitties_account_id,
+                market_account_id,
+                KittyId::from(1),
+            )
+            .await;
+
+            Ok(())
+        }
+
+        /// Instantiates the sale hook mock and lets Alice make it the market's sale hook.
+        async fn set_sale_hook(
+            client: &mut ink_e2e::Client<ink_e2e::PolkadotConfig, ink::env::DefaultEnvironment>,
+            market_account_id: AccountId,
+            revert: bool,
+        ) -> AccountId {
+            let hook_account_id = client
+                .instantiate(
+                    "sale_hook_mock",
+                    &ink_e2e::alice(),
+                    SaleHookMockRef::new(revert),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate sale_hook_mock failed")
+                .account_id;
+
+            let set_sale_hook_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.set_sale_hook(Some(hook_account_id)));
+            client
+                .call(&ink_e2e::alice(), set_sale_hook_msg, 0, None)
+                .await
+                .expect("set_sale_hook failed");
+
+            hook_account_id
+        }
+
+        /// Lets Alice list kitty `kitty_id` for 1_000 coins and Bob buy it with coins from
+        /// Alice.
+        async fn buy_kitty(
+            client: &mut ink_e2e::Client<ink_e2e::PolkadotConfig, ink::env::DefaultEnvironment>,
+            coin_account_id: AccountId,
+            kitties_account_id: AccountId,
+            market_account_id: AccountId,
+            kitty_id: KittyId,
+        ) {
+            let bob_acc = ink_e2e::account_id(AccountKeyring::Bob);
+            approve_market(client, kitties_account_id, market_account_id, kitty_id).await;
+            let list_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.list_for_sale(kitty_id, 1_000, None));
+            client
+                .call(&ink_e2e::alice(), list_msg, 0, None)
+                .await
+                .expect("list_for_sale failed");
+
+            let transfer_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.transfer(bob_acc, 1_000));
+            client
+                .call(&ink_e2e::alice(), transfer_msg, 0, None)
+                .await
+                .expect("transfer failed");
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(market_account_id, 1_000));
+            client
+                .call(&ink_e2e::bob(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+
+            let buy_msg = build_message::<KittyMarketRef>(market_account_id.clone())
+                .call(|kitty_market| kitty_market.buy(kitty_id));
+            client
+                .call(&ink_e2e::bob(), buy_msg, 0, None)
+                .await
+                .expect("buy failed");
+
+            let owner_of_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.owner_of(kitty_id));
+            let owner = client
+                .call_dry_run(&ink_e2e::bob(), &owner_of_msg, 0, None)
+                .await;
+            assert_eq!(owner.return_value(), Some(bob_acc));
+        }
+
+        /// Allowlists the market on Kitties and lets Alice pay for one more kitty.
+        async fn allow_minting(
+            client: &mut ink_e2e::Client<ink_e2e::PolkadotConfig, ink::env::DefaultEnvironment>,
+            coin_account_id: AccountId,
+            kitties_account_id: AccountId,
+            market_account_id: AccountId,
+        ) {
+            let set_market_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.set_market(Some(market_account_id)));
+            client
+                .call(&ink_e2e::alice(), set_market_msg, 0, None)
+                .await
+                .expect("set_market failed");
+
+            let approve_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.approve(kitties_account_id, MINT_PRICE));
+            client
+                .call(&ink_e2e::alice(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+        }
+
+        /// Returns the kitty coin balance of `account`.
+        async fn balance_of(
+            client: &mut ink_e2e::Client<ink_e2e::PolkadotConfig, ink::env::DefaultEnvironment>,
+            coin_account_id: AccountId,
+            account: AccountKeyring,
+        ) -> u128 {
+            let account = ink_e2e::account_id(account);
+            let balance_of_msg = build_message::<KittyCoinRef>(coin_account_id.clone())
+                .call(|kitty_coin| kitty_coin.balance_of(account));
+            client
+                .call_dry_run(&ink_e2e::alice(), &balance_of_msg, 0, None)
+                .await
+                .return_value()
+        }
+
+        /// Lets Alice approve the market for kitty `id`.
+        async fn approve_market(
+            client: &mut ink_e2e::Client<ink_e2e::PolkadotConfig, ink::env::DefaultEnvironment>,
+            kitties_account_id: AccountId,
+            market_account_id: AccountId,
+            id: KittyId,
+        ) {
+            let approve_msg = build_message::<KittiesRef>(kitties_account_id.clone())
+                .call(|kitties| kitties.approve(market_account_id, id));
+            client
+                .call(&ink_e2e::alice(), approve_msg, 0, None)
+                .await
+                .expect("approve failed");
+        }
+    }
 }
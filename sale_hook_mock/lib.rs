@@ -0,0 +1,75 @@
+//! A sale and transfer hook for testing the kitty contracts. It remembers the last
+//! sale and transfer it was notified about, or reverts every notification.
+
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+pub use sale_hook_mock::{SaleHookMock, SaleHookMockRef};
+
+#[ink::contract]
+mod sale_hook_mock {
+    use trait_erc721::{KittyId, TKittySaleHook, TKittyTransferHook};
+
+    /// A sale as kitty id, seller, buyer and price.
+    pub type Sale = (KittyId, AccountId, AccountId, u128);
+
+    /// A transfer as kitty id, sender and receiver.
+    pub type Transfer = (KittyId, AccountId, AccountId);
+
+    #[ink(storage)]
+    pub struct SaleHookMock {
+        /// Whether notifications revert.
+        revert: bool,
+        /// Last sale notified.
+        last_sale: Option<Sale>,
+        /// Last transfer notified.
+        last_transfer: Option<Transfer>,
+    }
+
+    impl SaleHookMock {
+        #[ink(constructor)]
+        pub fn new(revert: bool) -> Self {
+            Self {
+                revert,
+                last_sale: None,
+                last_transfer: None,
+            }
+        }
+
+        /// Returns the last sale notified.
+        #[ink(message)]
+        pub fn last_sale(&self) -> Option<Sale> {
+            self.last_sale
+        }
+
+        /// Returns the last transfer notified.
+        #[ink(message)]
+        pub fn last_transfer(&self) -> Option<Transfer> {
+            self.last_transfer
+        }
+    }
+
+    impl TKittySaleHook for SaleHookMock {
+        #[ink(message)]
+        fn on_kitty_sold(
+            &mut self,
+            kitty_id: KittyId,
+            seller: AccountId,
+            buyer: AccountId,
+            price: u128,
+        ) {
+            if self.revert {
+                panic!("sale hook reverted");
+            }
+            self.last_sale = Some((kitty_id, seller, buyer, price));
+        }
+    }
+
+    impl TKittyTransferHook for SaleHookMock {
+        #[ink(message)]
+        fn on_kitty_transferred(&mut self, kitty_id: KittyId, from: AccountId, to: AccountId) {
+            if self.revert {
+                panic!("transfer hook reverted");
+            }
+            self.last_transfer = Some((kitty_id, from, to));
+        }
+    }
+}
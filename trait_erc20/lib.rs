@@ -1,11 +1,18 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 use ink::env::*;
+use ink::prelude::string::String;
 
 #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
 #[cfg_attr(feature= "std", derive(scale_info::TypeInfo))]
 pub enum Error {
     BalanceTooLow,
     AllowanceTooLow,
+    NotOwner,
+    Overflow,
+    CapExceeded,
+    PermitExpired,
+    InvalidSignature,
+    Paused,
 }
 
 pub type Result<T> = core::result::Result<T, Error>;
@@ -25,14 +32,29 @@ pub trait TERC20 {
     fn balance_of(&self, who: AccountId) -> Balance;
 
     /// Returns the balance of the spender is still allowed to withdraw from the caller account.
+    ///
+    /// Deprecated: only reads allowances granted by the caller, use `allowance` instead.
     #[ink(message)]
     fn allowances_of(&self, spender: AccountId) -> Balance;
 
+    /// Returns the balance `spender` is still allowed to withdraw from the `owner` account.
+    #[ink(message)]
+    fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance;
+
     /// Allows `spender` to withdraw from the caller's account multiple times, up to
     /// the `value` amount.
     #[ink(message)]
     fn approve(&mut self, spender: AccountId, value: Balance) -> Result<()>;
 
+    /// Raises the allowance of `spender` from the caller's account by `delta`.
+    #[ink(message)]
+    fn increase_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()>;
+
+    /// Lowers the allowance of `spender` from the caller's account by `delta`,
+    /// down to zero at most.
+    #[ink(message)]
+    fn decrease_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()>;
+
     /// Transfers the token from the caller to the given destination.
     #[ink(message)]
     fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()>;
@@ -41,4 +63,19 @@ pub trait TERC20 {
     /// Caller has to hold an approval with enough fund to spend from the sender
     #[ink(message)]
     fn transfer_from(&mut self, from: AccountId, to: AccountId, value: Balance) -> Result<()>;
+}
+
+#[ink::trait_definition]
+pub trait TERC20Metadata {
+    /// Returns the name of the token.
+    #[ink(message)]
+    fn name(&self) -> String;
+
+    /// Returns the symbol of the token.
+    #[ink(message)]
+    fn symbol(&self) -> String;
+
+    /// Returns the number of decimals used to display token amounts.
+    #[ink(message)]
+    fn decimals(&self) -> u8;
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 use ink::env::*;
+use ink::prelude::vec::Vec;
 
 #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode, Copy, Clone)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
@@ -12,14 +13,65 @@ pub enum Error {
     CannotFetchValue,
     NotAllowed,
     CoinTransferFail,
+    SafeTransferCheckFailed,
+    NotContractOwner,
+    LockedForMarket,
+    MigrationClosed,
+    InvalidBasisPoints,
+    MaxSupplyReached,
+    RecipientNotAllowed,
+    KittyOnCooldown,
+    ContractPaused,
+    HoldingLimitReached,
+    IngredientBurnFail,
+    PriceExceedsMax,
+    TokenFrozen,
+    PriceOverflow,
 }
 
 pub type Result<T> = core::result::Result<T, Error>;
 
 /// A token ID.
-pub type KittyId = u32;
+///
+/// A newtype rather than a bare `u32`, so ids can't be mixed up with counts or balances
+/// by accident. Convert with `From` and `Into`. It encodes exactly like the `u32` it wraps.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    scale::Encode,
+    scale::Decode,
+)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct KittyId(u32);
+
+impl From<u32> for KittyId {
+    fn from(id: u32) -> Self {
+        Self(id)
+    }
+}
+
+impl From<KittyId> for u32 {
+    fn from(id: KittyId) -> Self {
+        id.0
+    }
+}
 type AccountId = <DefaultEnvironment as ::ink::env::Environment>::AccountId;
 
+/// Value a receiver contract returns from `on_erc721_received` to accept a token.
+///
+/// This is the ERC-721 `onERC721Received(address,address,uint256,bytes)` selector.
+pub const ON_ERC721_RECEIVED: [u8; 4] = [0x15, 0x0b, 0x7a, 0x02];
+
 #[ink::trait_definition]
 pub trait TERC721 {
     /// Returns the balance of the owner.
@@ -56,11 +108,102 @@ pub trait TERC721 {
     #[ink(message)]
     fn transfer_from(&mut self, from: AccountId, to: AccountId, id: KittyId) -> Result<()>;
 
+    /// Transfer approved or owned token, checking that a contract recipient accepts it.
+    ///
+    /// If `to` is a contract, its `on_erc721_received` hook is called with `data` and
+    /// the transfer reverts unless it returns `ON_ERC721_RECEIVED`.
+    #[ink(message)]
+    fn safe_transfer_from(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        id: KittyId,
+        data: Vec<u8>,
+    ) -> Result<()>;
+
     /// Creates a new token.
     #[ink(message)]
     fn mint(&mut self, id: KittyId) -> Result<()>;
 
-    /// Deletes an existing token. Only the owner can burn the token.
+    /// Deletes an existing token. The owner, the approved account or an operator of the
+    /// owner can burn the token.
     #[ink(message)]
     fn burn(&mut self, id: KittyId) -> Result<()>;
 }
+
+#[ink::trait_definition]
+pub trait TERC721Receiver {
+    /// Handles the receipt of a token sent with `safe_transfer_from`.
+    ///
+    /// Must return `ON_ERC721_RECEIVED` to accept the token.
+    #[ink(message)]
+    fn on_erc721_received(
+        &mut self,
+        operator: AccountId,
+        from: AccountId,
+        id: KittyId,
+        data: Vec<u8>,
+    ) -> [u8; 4];
+}
+
+#[ink::trait_definition]
+pub trait TKittiesMarket {
+    /// Locks or unlocks a listed token so its owner cannot change its approval or
+    /// transfer it. Only the allowlisted market can call this.
+    #[ink(message)]
+    fn set_locked_for_market(&mut self, id: KittyId, locked: bool) -> Result<()>;
+
+    /// Burns a listed token whatever its owner. Only the allowlisted market can call this.
+    #[ink(message)]
+    fn burn_for_market(&mut self, id: KittyId) -> Result<()>;
+
+    /// Mints token `id` to `to`, which pays for it, and approves the market for it.
+    /// Only the allowlisted market can call this.
+    #[ink(message)]
+    fn mint_for_market(&mut self, id: KittyId, to: AccountId) -> Result<()>;
+}
+
+#[ink::trait_definition]
+pub trait TERC2981 {
+    /// Returns the royalty receiver and the royalty amount owed for selling token `id`
+    /// at `sale_price`.
+    #[ink(message)]
+    fn royalty_info(&self, id: KittyId, sale_price: u128) -> (AccountId, u128);
+}
+
+#[ink::trait_definition]
+pub trait TKittySaleHook {
+    /// Handles the sale of token `kitty_id` by `seller` to `buyer` at `price`.
+    #[ink(message)]
+    fn on_kitty_sold(
+        &mut self,
+        kitty_id: KittyId,
+        seller: AccountId,
+        buyer: AccountId,
+        price: u128,
+    );
+}
+
+#[ink::trait_definition]
+pub trait TKittyTransferHook {
+    /// Handles the transfer of token `kitty_id` from `from` to `to`, which is the zero
+    /// address if the token was burned.
+    #[ink(message)]
+    fn on_kitty_transferred(&mut self, kitty_id: KittyId, from: AccountId, to: AccountId);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale::{Decode, Encode};
+
+    #[test]
+    fn kitty_id_encodes_like_u32() {
+        for id in [0, 1, 0x0102_0304, u32::MAX] {
+            let encoded = KittyId::from(id).encode();
+            assert_eq!(encoded, id.encode());
+            assert_eq!(KittyId::decode(&mut &encoded[..]), Ok(KittyId::from(id)));
+            assert_eq!(u32::from(KittyId::decode(&mut &encoded[..]).unwrap()), id);
+        }
+    }
+}